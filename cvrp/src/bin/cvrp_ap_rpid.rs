@@ -0,0 +1,402 @@
+use clap::Parser;
+use cvrp::{BoundChoice, CommonArgs, FleetSizeArgs, RoundedInstance, SolverChoice};
+use fixedbitset::FixedBitSet;
+use io_util::AssignmentSolution;
+use rpid::prelude::*;
+use rpid::{algorithms, io, solvers, timer::Timer};
+use std::cell::Cell;
+use std::cmp::{self, Ordering};
+use std::iter;
+use std::rc::Rc;
+
+#[cfg(not(target_env = "msvc"))]
+use tikv_jemallocator::Jemalloc;
+
+#[cfg(not(target_env = "msvc"))]
+#[global_allocator]
+static GLOBAL: Jemalloc = Jemalloc;
+
+#[derive(Debug, Parser)]
+struct Args {
+    #[command(flatten)]
+    common: CommonArgs,
+    #[command(flatten)]
+    vehicles: FleetSizeArgs,
+    #[arg(long, value_enum, default_value_t = BoundChoice::Assignment, help = "Dual bound")]
+    bound: BoundChoice,
+    #[arg(
+        long,
+        action,
+        help = "Improve new solutions found by CABS with local search"
+    )]
+    local_search: bool,
+    #[arg(long, help = "File to save the solution in the CVRPLIB format")]
+    solution: Option<String>,
+}
+
+struct Cvrp {
+    instance: RoundedInstance,
+    depot: usize,
+    n_vehicles: i32,
+    min_to: Vec<i32>,
+    min_from: Vec<i32>,
+    use_assignment_bound: bool,
+}
+
+impl Cvrp {
+    fn new(instance: RoundedInstance, bound: BoundChoice) -> Self {
+        // Multiple depots are rejected before creating the model.
+        let depot = instance.depots[0];
+        let n_vehicles = instance.get_total_vehicles() as i32;
+        let min_to = algorithms::take_column_wise_min_with_option(&instance.distances)
+            .map(|x| x.unwrap())
+            .collect();
+        let min_from = algorithms::take_row_wise_min_with_option(&instance.distances)
+            .map(|x| x.unwrap())
+            .collect();
+
+        Self {
+            instance,
+            depot,
+            n_vehicles,
+            min_to,
+            min_from,
+            use_assignment_bound: matches!(bound, BoundChoice::Assignment),
+        }
+    }
+}
+
+struct CvrpState {
+    unvisited: FixedBitSet,
+    current: usize,
+    load: i32,
+    n_vehicles: i32,
+    length: i32,
+    assignment: Option<AssignmentSolution>,
+}
+
+impl Cvrp {
+    fn check_feasibility(&self, state: &CvrpState) -> bool {
+        let remaining_demand = state
+            .unvisited
+            .ones()
+            .map(|i| self.instance.demands[i])
+            .sum::<i32>();
+
+        (self.n_vehicles - state.n_vehicles + 1) * self.instance.capacity
+            >= (state.load + remaining_demand)
+    }
+
+    // Assigns a successor to the current node and each unvisited node,
+    // where each unvisited node and the depot have exactly one predecessor.
+    // A customer can be reached via the depot if a vehicle is still available.
+    fn solve_assignment_problem(
+        &self,
+        state: &CvrpState,
+        warm_start: &AssignmentSolution,
+    ) -> Option<AssignmentSolution> {
+        let depot = self.depot;
+        let distances = &self.instance.distances;
+        let rows = iter::once(state.current)
+            .chain(state.unvisited.ones())
+            .collect::<Vec<_>>();
+        let columns = state
+            .unvisited
+            .ones()
+            .chain(iter::once(depot))
+            .collect::<Vec<_>>();
+        let is_last = state.unvisited.is_clear();
+        let can_use_vehicle = state.n_vehicles < self.n_vehicles;
+
+        io_util::solve_assignment_problem(
+            &rows,
+            &columns,
+            |i, j| {
+                if j == depot {
+                    return if i == state.current && !is_last {
+                        None
+                    } else {
+                        distances[i][depot]
+                    };
+                }
+
+                let direct = if i == state.current
+                    && state.load + self.instance.demands[j] > self.instance.capacity
+                {
+                    None
+                } else {
+                    distances[i][j]
+                };
+                let via_depot = match (can_use_vehicle, distances[i][depot], distances[depot][j]) {
+                    (true, Some(to_depot), Some(from_depot)) => Some(to_depot + from_depot),
+                    _ => None,
+                };
+
+                match (direct, via_depot) {
+                    (Some(direct), Some(via_depot)) => Some(cmp::min(direct, via_depot)),
+                    (direct, None) => direct,
+                    (None, via_depot) => via_depot,
+                }
+            },
+            warm_start,
+        )
+    }
+
+    // Solves the assignment problem of a successor warm-started by its parent.
+    // Returns `None` if the successor is infeasible.
+    fn with_assignment(&self, parent: &CvrpState, mut successor: CvrpState) -> Option<CvrpState> {
+        if let Some(assignment) = &parent.assignment {
+            successor.assignment = Some(self.solve_assignment_problem(&successor, assignment)?);
+        }
+
+        Some(successor)
+    }
+}
+
+impl Dp for Cvrp {
+    type State = CvrpState;
+    type CostType = i32;
+
+    fn get_target(&self) -> Self::State {
+        let depot = self.depot;
+        let mut unvisited = FixedBitSet::with_capacity(self.instance.nodes.len());
+        unvisited.insert_range(..);
+        unvisited.remove(depot);
+
+        let mut target = CvrpState {
+            unvisited,
+            current: depot,
+            load: 0,
+            n_vehicles: 1,
+            length: 0,
+            assignment: None,
+        };
+
+        if self.use_assignment_bound {
+            let n = self.instance.nodes.len();
+            target.assignment = self.solve_assignment_problem(&target, &AssignmentSolution::new(n));
+        }
+
+        target
+    }
+
+    fn get_successors(
+        &self,
+        state: &Self::State,
+    ) -> impl IntoIterator<Item = (Self::State, Self::CostType, usize)> {
+        let mut successors = state
+            .unvisited
+            .ones()
+            .filter_map(|next| {
+                if let Some(distance) = self.instance.distances[state.current][next] {
+                    let load = state.load + self.instance.demands[next];
+
+                    if load <= self.instance.capacity {
+                        let length = self.instance.extend_route_length(
+                            state.length,
+                            distance,
+                            next,
+                            self.depot,
+                        )?;
+                        let mut unvisited = state.unvisited.clone();
+                        unvisited.remove(next);
+                        let successor = CvrpState {
+                            unvisited,
+                            current: next,
+                            load,
+                            n_vehicles: state.n_vehicles,
+                            length,
+                            assignment: None,
+                        };
+
+                        if self.check_feasibility(&successor) {
+                            let successor = self.with_assignment(state, successor)?;
+
+                            Some((successor, distance, next))
+                        } else {
+                            None
+                        }
+                    } else {
+                        None
+                    }
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if state.n_vehicles < self.n_vehicles {
+            successors.extend(state.unvisited.ones().filter_map(|next| {
+                if let (Some(distance_to_depot), Some(distance_from_depot)) = (
+                    self.instance.distances[state.current][self.depot],
+                    self.instance.distances[self.depot][next],
+                ) {
+                    let length = self.instance.extend_route_length(
+                        0,
+                        distance_from_depot,
+                        next,
+                        self.depot,
+                    )?;
+                    let mut unvisited = state.unvisited.clone();
+                    unvisited.remove(next);
+                    let successor = CvrpState {
+                        unvisited,
+                        current: next,
+                        load: self.instance.demands[next],
+                        n_vehicles: state.n_vehicles + 1,
+                        length,
+                        assignment: None,
+                    };
+
+                    if self.check_feasibility(&successor) {
+                        let successor = self.with_assignment(state, successor)?;
+                        let weight = distance_to_depot + distance_from_depot;
+
+                        Some((successor, weight, self.instance.nodes.len() + next))
+                    } else {
+                        None
+                    }
+                } else {
+                    None
+                }
+            }))
+        }
+
+        successors
+    }
+
+    fn get_base_cost(&self, state: &Self::State) -> Option<Self::CostType> {
+        if state.unvisited.is_clear() {
+            self.instance.distances[state.current][self.depot]
+        } else {
+            None
+        }
+    }
+}
+
+impl Dominance for Cvrp {
+    type State = CvrpState;
+    type Key = (FixedBitSet, usize);
+
+    fn get_key(&self, state: &Self::State) -> Self::Key {
+        (state.unvisited.clone(), state.current)
+    }
+
+    fn compare(&self, a: &Self::State, b: &Self::State) -> Option<Ordering> {
+        if a.load == b.load && a.n_vehicles == b.n_vehicles && a.length == b.length {
+            Some(Ordering::Equal)
+        } else if a.load <= b.load && a.n_vehicles <= b.n_vehicles && a.length <= b.length {
+            Some(Ordering::Greater)
+        } else if a.load >= b.load && a.n_vehicles >= b.n_vehicles && a.length >= b.length {
+            Some(Ordering::Less)
+        } else {
+            None
+        }
+    }
+}
+
+impl Bound for Cvrp {
+    type State = CvrpState;
+    type CostType = i32;
+
+    fn get_dual_bound(&self, state: &Self::State) -> Option<Self::CostType> {
+        if self.use_assignment_bound {
+            return state.assignment.as_ref().map(|assignment| assignment.cost);
+        }
+
+        let bound_to =
+            state.unvisited.ones().map(|i| self.min_to[i]).sum::<i32>() + self.min_to[self.depot];
+        let bound_from = state
+            .unvisited
+            .ones()
+            .map(|i| self.min_from[i])
+            .sum::<i32>()
+            + self.min_from[state.current];
+
+        Some(cmp::max(bound_to, bound_from))
+    }
+}
+
+fn main() {
+    let timer = Timer::default();
+    let args = Args::parse();
+
+    let mut instance = RoundedInstance::load(
+        &args.common.input_file,
+        &args.vehicles.number,
+        args.vehicles.fleet_size,
+    )
+    .unwrap();
+
+    if instance.depots.len() > 1 {
+        println!("Use cvrp_rpid, cvrp_mst_rpid, or cvrp_dypdl for multiple depots");
+
+        return;
+    }
+
+    println!("Number of vehicles: {}", instance.get_total_vehicles());
+
+    if args.common.reduce_edges {
+        instance.reduce_edges();
+    }
+
+    let cvrp = Cvrp::new(instance.clone(), args.bound);
+
+    let parameters = SearchParameters {
+        time_limit: Some(args.common.time_limit),
+        ..Default::default()
+    };
+    let solution = match args.common.solver {
+        SolverChoice::Cabs if args.local_search => {
+            let cabs_parameters = CabsParameters::default();
+            let primal_bound = Rc::new(Cell::new(None));
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let mut solver = io_util::create_cabs_with_shared_primal_bound(
+                cvrp,
+                parameters,
+                cabs_parameters,
+                primal_bound.clone(),
+            );
+            io_util::run_solver_with_local_search_and_dump_solution_history(
+                &mut solver,
+                &args.common.history,
+                OptimizationMode::Minimization,
+                &primal_bound,
+                |transitions| {
+                    let tours = instance.transitions_to_tours(transitions);
+                    instance
+                        .improve_tours(&tours)
+                        .map(|(cost, tours)| (cost, instance.tours_to_transitions(&tours)))
+                },
+            )
+            .unwrap()
+        }
+        SolverChoice::Cabs => {
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let cabs_parameters = CabsParameters::default();
+            let mut solver = solvers::create_cabs(cvrp, parameters, cabs_parameters);
+            io::run_solver_and_dump_solution_history(&mut solver, &args.common.history).unwrap()
+        }
+        SolverChoice::Astar => {
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let mut solver = solvers::create_astar(cvrp, parameters);
+            io::run_solver_and_dump_solution_history(&mut solver, &args.common.history).unwrap()
+        }
+    };
+    io::print_solution_statistics(&solution);
+
+    if let Some(cost) = solution.cost {
+        let tours = instance.transitions_to_tours(&solution.transitions);
+        instance.print_solution(&tours);
+
+        if instance.validate(&tours, cost) {
+            println!("The solution is valid.");
+        } else {
+            println!("The solution is invalid.");
+        }
+
+        if let Some(filename) = &args.solution {
+            instance.write_solution(filename, &tours, cost).unwrap();
+        }
+    }
+}
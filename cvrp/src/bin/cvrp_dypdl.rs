@@ -1,14 +1,12 @@
 use clap::Parser;
-use cvrp::{Args, RoundedInstance, SolverChoice};
+use cvrp::{CommonArgs, FleetSizeArgs, RoundedInstance, SolverChoice};
 use dypdl::prelude::*;
 use dypdl_heuristic_search::{
     create_caasdy, create_dual_bound_cabs, BeamSearchParameters, CabsParameters, FEvaluatorType,
     Parameters,
 };
-use regex::Regex;
 use rpid::{algorithms, timer::Timer};
 use std::rc::Rc;
-use tsplib_parser::Instance;
 
 #[cfg(not(target_env = "msvc"))]
 use tikv_jemallocator::Jemalloc;
@@ -17,44 +15,74 @@ use tikv_jemallocator::Jemalloc;
 #[global_allocator]
 static GLOBAL: Jemalloc = Jemalloc;
 
+#[derive(Debug, Parser)]
+struct Args {
+    #[command(flatten)]
+    common: CommonArgs,
+    #[command(flatten)]
+    vehicles: FleetSizeArgs,
+    #[arg(long, help = "File to save the solution in the CVRPLIB format")]
+    solution: Option<String>,
+}
+
 fn main() {
     let timer = Timer::default();
     let args = Args::parse();
 
-    let filepath = args.input_file;
-    let filename = filepath.split('/').last().unwrap();
-
-    let re = Regex::new(r".+k(\d+).+").unwrap();
-    let n_vehicles = re.captures(filename).unwrap()[1].parse().unwrap();
+    let mut instance = RoundedInstance::load(
+        &args.common.input_file,
+        &args.vehicles.number,
+        args.vehicles.fleet_size,
+    )
+    .unwrap();
+    println!("Number of vehicles: {}", instance.get_total_vehicles());
 
-    let instance = Instance::load(&filepath).unwrap();
-    let mut instance = RoundedInstance::new(instance, n_vehicles).unwrap();
-    let n_vehicles = n_vehicles as i32;
-
-    if args.reduce_edges {
+    if args.common.reduce_edges {
         instance.reduce_edges();
     }
 
-    let depot = instance.depot;
+    let depots = instance.depots.clone();
 
     let mut model = Model::default();
 
     let n = instance.nodes.len();
     let customer = model.add_object_type("customer", n).unwrap();
 
-    let unvisited = (0..n).filter(|&i| i != depot).collect::<Vec<_>>();
+    let unvisited = (0..n).filter(|i| !depots.contains(i)).collect::<Vec<_>>();
     let unvisited = model.create_set(customer, &unvisited).unwrap();
     let unvisited = model
         .add_set_variable("unvisited", customer, unvisited)
         .unwrap();
     let current = model
-        .add_element_variable("current", customer, depot)
+        .add_element_variable("current", customer, depots[0])
+        .unwrap();
+    let depot = model
+        .add_element_variable("depot", customer, depots[0])
         .unwrap();
     let load = model
         .add_integer_resource_variable("load", true, 0)
         .unwrap();
-    let k = model.add_integer_resource_variable("k", true, 1).unwrap();
+    let k = depots
+        .iter()
+        .enumerate()
+        .map(|(i, d)| {
+            let initial = if i == 0 { 1 } else { 0 };
 
+            model
+                .add_integer_resource_variable(format!("k{}", d), true, initial)
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    let length = model
+        .add_integer_resource_variable("length", true, 0)
+        .unwrap();
+
+    let connected = instance
+        .distances
+        .iter()
+        .map(|row| row.iter().map(|&x| x.is_some()).collect())
+        .collect();
+    let connected = model.add_table_2d("connected", connected).unwrap();
     let distances = instance
         .distances
         .iter()
@@ -62,7 +90,7 @@ fn main() {
         .collect();
     let distances = model.add_table_2d("distances", distances).unwrap();
 
-    for next in (0..n).filter(|&i| i != depot) {
+    for next in (0..n).filter(|i| !depots.contains(i)) {
         let mut visit = Transition::new(format!("{}", next));
         visit.set_cost(distances.element(current, next) + IntegerExpression::Cost);
 
@@ -72,6 +100,7 @@ fn main() {
             .add_effect(load, load + instance.demands[next])
             .unwrap();
 
+        visit.add_precondition(connected.element(current, next));
         visit.add_precondition(unvisited.contains(next));
         visit.add_precondition(Condition::comparison_i(
             ComparisonOperator::Le,
@@ -79,63 +108,90 @@ fn main() {
             instance.capacity,
         ));
 
+        if let Some(max_route_length) = instance.max_route_length {
+            let new_length =
+                length + distances.element(current, next) + instance.service_times[next];
+            visit.add_effect(length, new_length.clone()).unwrap();
+            visit.add_precondition(Condition::comparison_i(
+                ComparisonOperator::Le,
+                new_length + distances.element(next, depot),
+                max_route_length,
+            ));
+        }
+
         model.add_forward_transition(visit).unwrap();
     }
 
-    let distances_via_depot = instance
-        .distances
-        .iter()
-        .map(|row| {
-            {
-                (0..n).map(|j| {
-                    if let (Some(distance_to_depot), Some(distance_from_depot)) =
-                        (row[depot], instance.distances[depot][j])
-                    {
-                        distance_to_depot + distance_from_depot
-                    } else {
-                        0
-                    }
-                })
-            }
-            .collect()
-        })
-        .collect();
-    let distances_via_depot = model
-        .add_table_2d("distances_via_depot", distances_via_depot)
-        .unwrap();
-
-    for next in (0..n).filter(|&i| i != depot) {
-        let mut visit_via_depot = Transition::new(format!("{}", n + next));
-        visit_via_depot
-            .set_cost(distances_via_depot.element(current, next) + IntegerExpression::Cost);
-
-        visit_via_depot
-            .add_effect(unvisited, unvisited.remove(next))
-            .unwrap();
-        visit_via_depot.add_effect(current, next).unwrap();
-        visit_via_depot
-            .add_effect(load, instance.demands[next])
-            .unwrap();
-        visit_via_depot.add_effect(k, k + 1).unwrap();
+    for (i, &new_depot) in depots.iter().enumerate() {
+        for next in (0..n).filter(|i| !depots.contains(i)) {
+            let Some(distance_from_depot) = instance.distances[new_depot][next] else {
+                continue;
+            };
 
-        visit_via_depot.add_precondition(unvisited.contains(next));
-        visit_via_depot.add_precondition(Condition::comparison_e(
-            ComparisonOperator::Ne,
-            current,
-            depot,
-        ));
-        visit_via_depot.add_precondition(Condition::comparison_i(
-            ComparisonOperator::Lt,
-            k,
-            n_vehicles,
-        ));
+            let mut visit_via_depot = Transition::new(format!("{}", n * (i + 1) + next));
+            visit_via_depot.set_cost(
+                distances.element(current, depot) + distance_from_depot + IntegerExpression::Cost,
+            );
+            visit_via_depot.add_effect(k[i], k[i] + 1).unwrap();
+            visit_via_depot.add_precondition(Condition::comparison_e(
+                ComparisonOperator::Ne,
+                current,
+                depot,
+            ));
+            visit_via_depot.add_precondition(connected.element(current, depot));
+
+            let mut transitions = vec![visit_via_depot];
+
+            // In the target state, the first route can start from another depot instead.
+            if i > 0 {
+                let mut start_from_depot = Transition::new(format!("{}", n * (i + 1) + next));
+                start_from_depot.set_cost(distance_from_depot + IntegerExpression::Cost);
+                start_from_depot.add_effect(k[0], k[0] - 1).unwrap();
+                start_from_depot.add_effect(k[i], k[i] + 1).unwrap();
+                start_from_depot.add_precondition(Condition::comparison_e(
+                    ComparisonOperator::Eq,
+                    current,
+                    depot,
+                ));
+                transitions.push(start_from_depot);
+            }
 
-        model.add_forward_transition(visit_via_depot).unwrap();
+            for mut transition in transitions {
+                transition
+                    .add_effect(unvisited, unvisited.remove(next))
+                    .unwrap();
+                transition.add_effect(current, next).unwrap();
+                transition.add_effect(depot, new_depot).unwrap();
+                transition.add_effect(load, instance.demands[next]).unwrap();
+
+                transition.add_precondition(unvisited.contains(next));
+                transition.add_precondition(Condition::comparison_i(
+                    ComparisonOperator::Lt,
+                    k[i],
+                    instance.n_vehicles[i] as i32,
+                ));
+
+                if let Some(max_route_length) = instance.max_route_length {
+                    let new_length = distance_from_depot + instance.service_times[next];
+                    transition.add_effect(length, new_length).unwrap();
+                    transition.add_precondition(Condition::comparison_i(
+                        ComparisonOperator::Le,
+                        new_length + distances.element(next, new_depot),
+                        max_route_length,
+                    ));
+                }
+
+                model.add_forward_transition(transition).unwrap();
+            }
+        }
     }
 
+    // A missing edge has distance zero in the table, so it must be excluded by preconditions.
+    let can_return = Condition::comparison_e(ComparisonOperator::Eq, current, depot)
+        | connected.element(current, depot);
     model
         .add_base_case_with_cost(
-            vec![unvisited.is_empty()],
+            vec![unvisited.is_empty(), can_return],
             distances.element(current, depot),
         )
         .unwrap();
@@ -143,27 +199,64 @@ fn main() {
     let demands = model
         .add_table_1d("demands", instance.demands.clone())
         .unwrap();
-    let total_remaining_capacity = (n_vehicles - k) * instance.capacity + instance.capacity;
+    let mut total_remaining_capacity = IntegerExpression::from(instance.capacity);
+
+    for (&k, &n_vehicles) in k.iter().zip(instance.n_vehicles.iter()) {
+        model
+            .add_state_constraint(Condition::comparison_i(
+                ComparisonOperator::Le,
+                k,
+                n_vehicles as i32,
+            ))
+            .unwrap();
+        total_remaining_capacity =
+            total_remaining_capacity + (n_vehicles as i32 - k) * instance.capacity;
+    }
+
     let total_remaining_demand = load + demands.sum(unvisited);
     model
         .add_state_constraint(Condition::comparison_i(
             ComparisonOperator::Ge,
             total_remaining_capacity,
-            total_remaining_demand,
+            total_remaining_demand.clone(),
         ))
         .unwrap();
 
+    // The total number of vehicles can be smaller than the sum over depots.
+    if let Some(max_total_vehicles) = instance.max_total_vehicles {
+        let max_total_vehicles = max_total_vehicles as i32;
+        let used_vehicles = k.iter().fold(IntegerExpression::from(0), |sum, &k| sum + k);
+        model
+            .add_state_constraint(Condition::comparison_i(
+                ComparisonOperator::Le,
+                used_vehicles.clone(),
+                max_total_vehicles,
+            ))
+            .unwrap();
+        model
+            .add_state_constraint(Condition::comparison_i(
+                ComparisonOperator::Ge,
+                (max_total_vehicles - used_vehicles + 1) * instance.capacity,
+                total_remaining_demand,
+            ))
+            .unwrap();
+    }
+
     let min_to = algorithms::take_column_wise_min_with_option(&instance.distances)
         .map(|x| x.unwrap())
         .collect::<Vec<_>>();
+    let min_to_depot = depots.iter().map(|&i| min_to[i]).min().unwrap();
     let min_to = model.add_table_1d("min_to", min_to).unwrap();
     model
-        .add_dual_bound(min_to.sum(unvisited) + min_to.element(depot))
+        .add_dual_bound(min_to.sum(unvisited) + min_to_depot)
         .unwrap();
 
-    let min_from = algorithms::take_row_wise_min_with_option(&instance.distances)
+    let mut min_from = algorithms::take_row_wise_min_with_option(&instance.distances)
         .map(|x| x.unwrap())
         .collect::<Vec<_>>();
+    // The first route can start from any depot.
+    let min_from_depot = depots.iter().map(|&i| min_from[i]).min().unwrap();
+    depots.iter().for_each(|&i| min_from[i] = min_from_depot);
     let min_from = model.add_table_1d("min_from", min_from).unwrap();
     model
         .add_dual_bound(min_from.sum(unvisited) + min_from.element(current))
@@ -172,11 +265,11 @@ fn main() {
     let model = Rc::new(model);
 
     let parameters = Parameters::<i32> {
-        time_limit: Some(args.time_limit),
+        time_limit: Some(args.common.time_limit),
         ..Default::default()
     };
 
-    let mut solver = match args.solver {
+    let mut solver = match args.common.solver {
         SolverChoice::Cabs => {
             let beam_search_parameters = BeamSearchParameters {
                 parameters,
@@ -198,21 +291,16 @@ fn main() {
     };
 
     let solution =
-        io_util::run_solver_and_dump_solution_history(&mut solver, &args.history).unwrap();
+        io_util::run_solver_and_dump_solution_history(&mut solver, &args.common.history).unwrap();
     io_util::print_solution_statistics(&solution);
 
     if let Some(cost) = solution.cost {
-        let mut tours = vec![vec![]];
-
-        for transition in solution.transitions {
-            let i = transition.get_full_name().parse::<usize>().unwrap();
-
-            if i >= n {
-                tours.push(vec![i - n]);
-            } else {
-                tours.last_mut().unwrap().push(i);
-            }
-        }
+        let transitions = solution
+            .transitions
+            .iter()
+            .map(|t| t.get_full_name().parse::<usize>().unwrap())
+            .collect::<Vec<_>>();
+        let tours = instance.transitions_to_tours(&transitions);
 
         instance.print_solution(&tours);
 
@@ -221,5 +309,9 @@ fn main() {
         } else {
             println!("The solution is invalid.");
         }
+
+        if let Some(filename) = &args.solution {
+            instance.write_solution(filename, &tours, cost).unwrap();
+        }
     }
 }
@@ -0,0 +1,362 @@
+use clap::Parser;
+use cvrp::{CommonArgs, FleetSizeChoice, RoundedInstance, SolverChoice, VehicleType};
+use fixedbitset::FixedBitSet;
+use rpid::prelude::*;
+use rpid::{io, solvers, timer::Timer};
+use std::cmp::{self, Ordering};
+
+#[cfg(not(target_env = "msvc"))]
+use tikv_jemallocator::Jemalloc;
+
+#[cfg(not(target_env = "msvc"))]
+#[global_allocator]
+static GLOBAL: Jemalloc = Jemalloc;
+
+#[derive(Debug, Parser)]
+struct Args {
+    #[command(flatten)]
+    common: CommonArgs,
+    #[arg(
+        long,
+        help = "File defining the vehicle types of a heterogeneous fleet"
+    )]
+    fleet: String,
+}
+
+struct FleetCvrp {
+    instance: RoundedInstance,
+    depot: usize,
+    vehicle_types: Vec<VehicleType>,
+    costs: Vec<Vec<Vec<Option<i32>>>>,
+    min_to: Vec<i32>,
+    min_from: Vec<i32>,
+}
+
+impl FleetCvrp {
+    fn new(instance: RoundedInstance, vehicle_types: Vec<VehicleType>) -> Self {
+        // Multiple depots are rejected before creating the model.
+        let depot = instance.depots[0];
+        let costs = vehicle_types
+            .iter()
+            .map(|vehicle_type| {
+                instance
+                    .distances
+                    .iter()
+                    .map(|row| {
+                        row.iter()
+                            .map(|d| d.map(|d| vehicle_type.compute_travel_cost(d)))
+                            .collect()
+                    })
+                    .collect::<Vec<Vec<_>>>()
+            })
+            .collect::<Vec<_>>();
+        let n = instance.nodes.len();
+        let min_to = (0..n)
+            .map(|j| {
+                costs
+                    .iter()
+                    .flat_map(|c| c.iter().filter_map(|row| row[j]))
+                    .min()
+                    .unwrap()
+            })
+            .collect();
+        let min_from = (0..n)
+            .map(|i| {
+                costs
+                    .iter()
+                    .flat_map(|c| c[i].iter().flatten())
+                    .copied()
+                    .min()
+                    .unwrap()
+            })
+            .collect();
+
+        Self {
+            instance,
+            depot,
+            vehicle_types,
+            costs,
+            min_to,
+            min_from,
+        }
+    }
+}
+
+struct FleetCvrpState {
+    unvisited: FixedBitSet,
+    current: usize,
+    load: i32,
+    length: i32,
+    vehicle_type: Option<usize>,
+    remaining: Vec<usize>,
+}
+
+impl FleetCvrp {
+    fn get_residual_capacity(&self, state: &FleetCvrpState) -> i32 {
+        state
+            .vehicle_type
+            .map_or(0, |t| self.vehicle_types[t].capacity - state.load)
+    }
+
+    fn get_remaining_demand(&self, state: &FleetCvrpState) -> i32 {
+        state
+            .unvisited
+            .ones()
+            .map(|i| self.instance.demands[i])
+            .sum()
+    }
+
+    fn check_feasibility(&self, state: &FleetCvrpState) -> bool {
+        let remaining_capacity = self.get_residual_capacity(state)
+            + state
+                .remaining
+                .iter()
+                .zip(self.vehicle_types.iter())
+                .map(|(&count, vehicle_type)| count as i32 * vehicle_type.capacity)
+                .sum::<i32>();
+
+        remaining_capacity >= self.get_remaining_demand(state)
+    }
+}
+
+impl Dp for FleetCvrp {
+    type State = FleetCvrpState;
+    type CostType = i32;
+
+    fn get_target(&self) -> Self::State {
+        let depot = self.depot;
+        let mut unvisited = FixedBitSet::with_capacity(self.instance.nodes.len());
+        unvisited.insert_range(..);
+        unvisited.remove(depot);
+
+        FleetCvrpState {
+            unvisited,
+            current: depot,
+            load: 0,
+            length: 0,
+            vehicle_type: None,
+            remaining: self.vehicle_types.iter().map(|t| t.count).collect(),
+        }
+    }
+
+    fn get_successors(
+        &self,
+        state: &Self::State,
+    ) -> impl IntoIterator<Item = (Self::State, Self::CostType, usize)> {
+        let depot = self.depot;
+        let mut successors = vec![];
+
+        if let Some(t) = state.vehicle_type {
+            successors.extend(state.unvisited.ones().filter_map(|next| {
+                let distance = self.instance.distances[state.current][next]?;
+                let load = state.load + self.instance.demands[next];
+
+                if load > self.vehicle_types[t].capacity {
+                    return None;
+                }
+
+                let length =
+                    self.instance
+                        .extend_route_length(state.length, distance, next, self.depot)?;
+                let mut unvisited = state.unvisited.clone();
+                unvisited.remove(next);
+                let successor = FleetCvrpState {
+                    unvisited,
+                    current: next,
+                    load,
+                    length,
+                    vehicle_type: Some(t),
+                    remaining: state.remaining.clone(),
+                };
+
+                if self.check_feasibility(&successor) {
+                    Some((successor, self.costs[t][state.current][next]?, next))
+                } else {
+                    None
+                }
+            }));
+        }
+
+        let cost_to_depot = if let Some(t) = state.vehicle_type {
+            self.costs[t][state.current][depot]
+        } else {
+            Some(0)
+        };
+
+        if let Some(cost_to_depot) = cost_to_depot {
+            let n = self.instance.nodes.len();
+
+            for (t, vehicle_type) in self.vehicle_types.iter().enumerate() {
+                if state.remaining[t] == 0 {
+                    continue;
+                }
+
+                successors.extend(state.unvisited.ones().filter_map(|next| {
+                    let distance_from_depot = self.instance.distances[depot][next]?;
+                    let load = self.instance.demands[next];
+
+                    if load > vehicle_type.capacity {
+                        return None;
+                    }
+
+                    let length = self.instance.extend_route_length(
+                        0,
+                        distance_from_depot,
+                        next,
+                        self.depot,
+                    )?;
+                    let mut unvisited = state.unvisited.clone();
+                    unvisited.remove(next);
+                    let mut remaining = state.remaining.clone();
+                    remaining[t] -= 1;
+                    let successor = FleetCvrpState {
+                        unvisited,
+                        current: next,
+                        load,
+                        length,
+                        vehicle_type: Some(t),
+                        remaining,
+                    };
+
+                    if self.check_feasibility(&successor) {
+                        let weight =
+                            cost_to_depot + vehicle_type.fixed_cost + self.costs[t][depot][next]?;
+
+                        Some((successor, weight, n * (t + 1) + next))
+                    } else {
+                        None
+                    }
+                }));
+            }
+        }
+
+        successors
+    }
+
+    fn get_base_cost(&self, state: &Self::State) -> Option<Self::CostType> {
+        if state.unvisited.is_clear() {
+            self.costs[state.vehicle_type?][state.current][self.depot]
+        } else {
+            None
+        }
+    }
+}
+
+impl Dominance for FleetCvrp {
+    type State = FleetCvrpState;
+    type Key = (FixedBitSet, usize, Option<usize>);
+
+    fn get_key(&self, state: &Self::State) -> Self::Key {
+        (state.unvisited.clone(), state.current, state.vehicle_type)
+    }
+
+    fn compare(&self, a: &Self::State, b: &Self::State) -> Option<Ordering> {
+        let mut ordering = a.load.cmp(&b.load).reverse();
+
+        for o in [b.length.cmp(&a.length)].into_iter().chain(
+            a.remaining
+                .iter()
+                .zip(b.remaining.iter())
+                .map(|(a, b)| a.cmp(b)),
+        ) {
+            match (ordering, o) {
+                (_, Ordering::Equal) => {}
+                (Ordering::Equal, o) => ordering = o,
+                (ordering, o) if ordering != o => return None,
+                _ => {}
+            }
+        }
+
+        Some(ordering)
+    }
+}
+
+impl Bound for FleetCvrp {
+    type State = FleetCvrpState;
+    type CostType = i32;
+
+    fn get_dual_bound(&self, state: &Self::State) -> Option<Self::CostType> {
+        let bound_to =
+            state.unvisited.ones().map(|i| self.min_to[i]).sum::<i32>() + self.min_to[self.depot];
+        let bound_from = state
+            .unvisited
+            .ones()
+            .map(|i| self.min_from[i])
+            .sum::<i32>()
+            + self.min_from[state.current];
+        let mut bound = cmp::max(bound_to, bound_from);
+
+        // At least one more vehicle is needed if the current one cannot serve the remaining demand.
+        if self.get_remaining_demand(state) > self.get_residual_capacity(state) {
+            bound += self
+                .vehicle_types
+                .iter()
+                .zip(state.remaining.iter())
+                .filter_map(|(vehicle_type, &count)| {
+                    if count > 0 {
+                        Some(vehicle_type.fixed_cost)
+                    } else {
+                        None
+                    }
+                })
+                .min()?;
+        }
+
+        Some(bound)
+    }
+}
+
+fn main() {
+    let timer = Timer::default();
+    let args = Args::parse();
+
+    let vehicle_types = VehicleType::read_fleet_from_file(&args.fleet).unwrap();
+    let mut instance =
+        RoundedInstance::load(&args.common.input_file, &[], FleetSizeChoice::Unlimited).unwrap();
+
+    if instance.depots.len() > 1 {
+        println!("Use cvrp_rpid, cvrp_mst_rpid, or cvrp_dypdl for multiple depots");
+
+        return;
+    }
+
+    instance.n_vehicles = vec![vehicle_types.iter().map(|t| t.count).sum()];
+    instance.capacity = vehicle_types.iter().map(|t| t.capacity).max().unwrap();
+    println!("Number of vehicles: {}", instance.get_total_vehicles());
+
+    if args.common.reduce_edges {
+        instance.reduce_edges();
+    }
+
+    let cvrp = FleetCvrp::new(instance.clone(), vehicle_types.clone());
+
+    let parameters = SearchParameters {
+        time_limit: Some(args.common.time_limit),
+        ..Default::default()
+    };
+    let solution = match args.common.solver {
+        SolverChoice::Cabs => {
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let cabs_parameters = CabsParameters::default();
+            let mut solver = solvers::create_cabs(cvrp, parameters, cabs_parameters);
+            io::run_solver_and_dump_solution_history(&mut solver, &args.common.history).unwrap()
+        }
+        SolverChoice::Astar => {
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let mut solver = solvers::create_astar(cvrp, parameters);
+            io::run_solver_and_dump_solution_history(&mut solver, &args.common.history).unwrap()
+        }
+    };
+    io::print_solution_statistics(&solution);
+
+    if let Some(cost) = solution.cost {
+        let tours = instance.transitions_to_fleet_tours(&solution.transitions);
+        instance.print_fleet_solution(&tours);
+
+        if instance.validate_with_fleet(&vehicle_types, &tours, cost) {
+            println!("The solution is valid.");
+        } else {
+            println!("The solution is invalid.");
+        }
+    }
+}
@@ -1,11 +1,11 @@
 use clap::Parser;
 use cvrp::{Args, RoundedInstance, SolverChoice};
 use fixedbitset::FixedBitSet;
-use regex::Regex;
 use rpid::prelude::*;
 use rpid::{algorithms, io, solvers, timer::Timer};
+use std::cell::Cell;
 use std::cmp::{self, Ordering};
-use tsplib_parser::Instance;
+use std::rc::Rc;
 
 #[cfg(not(target_env = "msvc"))]
 use tikv_jemallocator::Jemalloc;
@@ -16,26 +16,42 @@ static GLOBAL: Jemalloc = Jemalloc;
 
 struct Cvrp {
     instance: RoundedInstance,
-    n_vehicles: i32,
+    n_vehicles: Vec<i32>,
+    total_vehicles: i32,
+    break_symmetry: bool,
+    break_reversal: bool,
     min_to: Vec<i32>,
     min_from: Vec<i32>,
+    min_to_depot: i32,
 }
 
 impl From<RoundedInstance> for Cvrp {
     fn from(instance: RoundedInstance) -> Self {
-        let n_vehicles = instance.n_vehicles as i32;
+        let n_vehicles = instance.n_vehicles.iter().map(|&k| k as i32).collect();
+        let total_vehicles = instance.get_total_vehicles() as i32;
         let min_to = algorithms::take_column_wise_min_with_option(&instance.distances)
             .map(|x| x.unwrap())
-            .collect();
-        let min_from = algorithms::take_row_wise_min_with_option(&instance.distances)
+            .collect::<Vec<_>>();
+        let mut min_from = algorithms::take_row_wise_min_with_option(&instance.distances)
             .map(|x| x.unwrap())
-            .collect();
+            .collect::<Vec<_>>();
+        let min_to_depot = instance.depots.iter().map(|&i| min_to[i]).min().unwrap();
+        // The first route can start from any depot.
+        let min_from_depot = instance.depots.iter().map(|&i| min_from[i]).min().unwrap();
+        instance
+            .depots
+            .iter()
+            .for_each(|&i| min_from[i] = min_from_depot);
 
         Self {
             instance,
             n_vehicles,
+            total_vehicles,
+            break_symmetry: false,
+            break_reversal: false,
             min_to,
             min_from,
+            min_to_depot,
         }
     }
 }
@@ -43,20 +59,58 @@ impl From<RoundedInstance> for Cvrp {
 struct CvrpState {
     unvisited: FixedBitSet,
     current: usize,
+    depot: usize,
     load: i32,
-    n_vehicles: i32,
+    n_vehicles: Vec<i32>,
+    length: i32,
+    first: usize,
+    required: Option<usize>,
 }
 
 impl Cvrp {
+    fn enable_symmetry_breaking(&mut self) {
+        self.break_symmetry = true;
+        // A route and its reverse have the same cost only if the distances are symmetric.
+        self.break_reversal = self.instance.is_symmetric();
+    }
+
+    fn get_required_customer(&self, unvisited: &FixedBitSet, next: usize) -> Option<usize> {
+        if self.break_symmetry {
+            unvisited.minimum().filter(|&i| i != next)
+        } else {
+            None
+        }
+    }
+
+    // Each route must visit the lowest-indexed customer unvisited at its start,
+    // and its first customer must not exceed the last one.
+    fn can_return_to_depot(&self, state: &CvrpState) -> bool {
+        state.required.is_none() && (!self.break_reversal || state.first <= state.current)
+    }
+
     fn check_feasibility(&self, state: &CvrpState) -> bool {
         let remaining_demand = state
             .unvisited
             .ones()
             .map(|i| self.instance.demands[i])
             .sum::<i32>();
-
-        (self.n_vehicles - state.n_vehicles + 1) * self.instance.capacity
-            >= (state.load + remaining_demand)
+        let used_vehicles = state.n_vehicles.iter().sum::<i32>();
+        // The total number of vehicles can be smaller than the sum over depots.
+        let remaining_vehicles = cmp::min(
+            self.n_vehicles
+                .iter()
+                .zip(state.n_vehicles.iter())
+                .map(|(n_vehicles, used)| n_vehicles - used)
+                .sum::<i32>(),
+            self.total_vehicles - used_vehicles,
+        );
+
+        self.n_vehicles
+            .iter()
+            .zip(state.n_vehicles.iter())
+            .all(|(n_vehicles, used)| used <= n_vehicles)
+            && used_vehicles <= self.total_vehicles
+            && (remaining_vehicles + 1) * self.instance.capacity >= (state.load + remaining_demand)
     }
 }
 
@@ -65,16 +119,29 @@ impl Dp for Cvrp {
     type CostType = i32;
 
     fn get_target(&self) -> Self::State {
-        let depot = self.instance.depot;
         let mut unvisited = FixedBitSet::with_capacity(self.instance.nodes.len());
         unvisited.insert_range(..);
-        unvisited.remove(depot);
+        self.instance
+            .depots
+            .iter()
+            .for_each(|&depot| unvisited.remove(depot));
+        let mut n_vehicles = vec![0; self.n_vehicles.len()];
+        n_vehicles[0] = 1;
+        let required = if self.break_symmetry {
+            unvisited.minimum()
+        } else {
+            None
+        };
 
         CvrpState {
             unvisited,
-            current: depot,
+            current: self.instance.depots[0],
+            depot: 0,
             load: 0,
-            n_vehicles: 1,
+            n_vehicles,
+            length: 0,
+            first: 0,
+            required,
         }
     }
 
@@ -82,6 +149,9 @@ impl Dp for Cvrp {
         &self,
         state: &Self::State,
     ) -> impl IntoIterator<Item = (Self::State, Self::CostType, usize)> {
+        let depot = self.instance.depots[state.depot];
+        // The route is empty only in the target state, where it can start from another depot instead.
+        let is_empty_route = state.current == depot;
         let mut successors = state
             .unvisited
             .ones()
@@ -90,13 +160,23 @@ impl Dp for Cvrp {
                     let load = state.load + self.instance.demands[next];
 
                     if load <= self.instance.capacity {
+                        let length = self.instance.extend_route_length(
+                            state.length,
+                            distance,
+                            next,
+                            depot,
+                        )?;
                         let mut unvisited = state.unvisited.clone();
                         unvisited.remove(next);
                         let successor = CvrpState {
                             unvisited,
                             current: next,
+                            depot: state.depot,
                             load,
-                            n_vehicles: state.n_vehicles,
+                            n_vehicles: state.n_vehicles.clone(),
+                            length,
+                            first: if is_empty_route { next } else { state.first },
+                            required: state.required.filter(|&i| i != next),
                         };
 
                         if self.check_feasibility(&successor) {
@@ -113,40 +193,72 @@ impl Dp for Cvrp {
             })
             .collect::<Vec<_>>();
 
-        if state.n_vehicles < self.n_vehicles {
-            successors.extend(state.unvisited.ones().filter_map(|next| {
-                if let (Some(distance_to_depot), Some(distance_from_depot)) = (
-                    self.instance.distances[state.current][self.instance.depot],
-                    self.instance.distances[self.instance.depot][next],
-                ) {
+        let distance_to_depot = if is_empty_route {
+            Some(0)
+        } else if !self.can_return_to_depot(state) {
+            None
+        } else {
+            self.instance.distances[state.current][depot]
+        };
+
+        if let Some(distance_to_depot) = distance_to_depot {
+            let n = self.instance.nodes.len();
+
+            for (k, &new_depot) in self.instance.depots.iter().enumerate() {
+                if is_empty_route && k == state.depot {
+                    continue;
+                }
+
+                let mut n_vehicles = state.n_vehicles.clone();
+
+                if is_empty_route {
+                    n_vehicles[state.depot] -= 1;
+                }
+
+                if n_vehicles[k] >= self.n_vehicles[k] {
+                    continue;
+                }
+
+                n_vehicles[k] += 1;
+
+                successors.extend(state.unvisited.ones().filter_map(|next| {
+                    let distance_from_depot = self.instance.distances[new_depot][next]?;
+                    let length = self.instance.extend_route_length(
+                        0,
+                        distance_from_depot,
+                        next,
+                        new_depot,
+                    )?;
                     let mut unvisited = state.unvisited.clone();
                     unvisited.remove(next);
                     let successor = CvrpState {
                         unvisited,
                         current: next,
+                        depot: k,
                         load: self.instance.demands[next],
-                        n_vehicles: state.n_vehicles + 1,
+                        n_vehicles: n_vehicles.clone(),
+                        length,
+                        first: next,
+                        required: self.get_required_customer(&state.unvisited, next),
                     };
 
                     if self.check_feasibility(&successor) {
                         let weight = distance_to_depot + distance_from_depot;
 
-                        Some((successor, weight, self.instance.nodes.len() + next))
+                        Some((successor, weight, n * (k + 1) + next))
                     } else {
                         None
                     }
-                } else {
-                    None
-                }
-            }))
+                }));
+            }
         }
 
         successors
     }
 
     fn get_base_cost(&self, state: &Self::State) -> Option<Self::CostType> {
-        if state.unvisited.is_clear() {
-            self.instance.distances[state.current][self.instance.depot]
+        if state.unvisited.is_clear() && self.can_return_to_depot(state) {
+            self.instance.distances[state.current][self.instance.depots[state.depot]]
         } else {
             None
         }
@@ -155,18 +267,36 @@ impl Dp for Cvrp {
 
 impl Dominance for Cvrp {
     type State = CvrpState;
-    type Key = (FixedBitSet, usize);
+    type Key = (FixedBitSet, usize, usize);
 
     fn get_key(&self, state: &Self::State) -> Self::Key {
-        (state.unvisited.clone(), state.current)
+        (state.unvisited.clone(), state.current, state.depot)
     }
 
     fn compare(&self, a: &Self::State, b: &Self::State) -> Option<Ordering> {
-        if a.load == b.load && a.n_vehicles == b.n_vehicles {
+        let fewer_vehicles = a
+            .n_vehicles
+            .iter()
+            .zip(b.n_vehicles.iter())
+            .all(|(a, b)| a <= b);
+        let more_vehicles = a
+            .n_vehicles
+            .iter()
+            .zip(b.n_vehicles.iter())
+            .all(|(a, b)| a >= b);
+
+        let same_route = a.required == b.required && (!self.break_reversal || a.first == b.first);
+        // A route is less restricted if it has no customer left to visit and its first customer is lower.
+        let less_restricted = (a.required.is_none() || a.required == b.required)
+            && (!self.break_reversal || a.first <= b.first);
+        let more_restricted = (b.required.is_none() || a.required == b.required)
+            && (!self.break_reversal || a.first >= b.first);
+
+        if a.load == b.load && a.n_vehicles == b.n_vehicles && a.length == b.length && same_route {
             Some(Ordering::Equal)
-        } else if a.load <= b.load && a.n_vehicles <= b.n_vehicles {
+        } else if a.load <= b.load && fewer_vehicles && a.length <= b.length && less_restricted {
             Some(Ordering::Greater)
-        } else if a.load >= b.load && a.n_vehicles >= b.n_vehicles {
+        } else if a.load >= b.load && more_vehicles && a.length >= b.length && more_restricted {
             Some(Ordering::Less)
         } else {
             None
@@ -179,8 +309,8 @@ impl Bound for Cvrp {
     type CostType = i32;
 
     fn get_dual_bound(&self, state: &Self::State) -> Option<Self::CostType> {
-        let bound_to = state.unvisited.ones().map(|i| self.min_to[i]).sum::<i32>()
-            + self.min_to[self.instance.depot];
+        let bound_to =
+            state.unvisited.ones().map(|i| self.min_to[i]).sum::<i32>() + self.min_to_depot;
         let bound_from = state
             .unvisited
             .ones()
@@ -196,51 +326,69 @@ fn main() {
     let timer = Timer::default();
     let args = Args::parse();
 
-    let filepath = args.input_file;
-    let filename = filepath.split('/').last().unwrap();
-
-    let re = Regex::new(r".+k(\d+).+").unwrap();
-    let n_vehicles = re.captures(filename).unwrap()[1].parse().unwrap();
-
-    let instance = Instance::load(&filepath).unwrap();
-    let mut instance = RoundedInstance::new(instance, n_vehicles).unwrap();
+    let mut instance = RoundedInstance::load(
+        &args.common.input_file,
+        &args.vehicles.number,
+        args.vehicles.fleet_size,
+    )
+    .unwrap();
+    println!("Number of vehicles: {}", instance.get_total_vehicles());
 
-    if args.reduce_edges {
+    if args.common.reduce_edges {
         instance.reduce_edges();
     }
 
-    let cvrp = Cvrp::from(instance.clone());
+    let mut cvrp = Cvrp::from(instance.clone());
+
+    if args.break_symmetry {
+        cvrp.enable_symmetry_breaking();
+    }
 
     let parameters = SearchParameters {
-        time_limit: Some(args.time_limit),
+        time_limit: Some(args.common.time_limit),
         ..Default::default()
     };
-    let solution = match args.solver {
+    let solution = match args.common.solver {
+        SolverChoice::Cabs if args.local_search => {
+            let cabs_parameters = CabsParameters::default();
+            let primal_bound = Rc::new(Cell::new(None));
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let mut solver = io_util::create_cabs_with_shared_primal_bound(
+                cvrp,
+                parameters,
+                cabs_parameters,
+                primal_bound.clone(),
+            );
+            io_util::run_solver_with_local_search_and_dump_solution_history(
+                &mut solver,
+                &args.common.history,
+                OptimizationMode::Minimization,
+                &primal_bound,
+                |transitions| {
+                    let tours = instance.transitions_to_tours(transitions);
+                    instance
+                        .improve_tours(&tours)
+                        .map(|(cost, tours)| (cost, instance.tours_to_transitions(&tours)))
+                },
+            )
+            .unwrap()
+        }
         SolverChoice::Cabs => {
             println!("Preparing time: {}s", timer.get_elapsed_time());
             let cabs_parameters = CabsParameters::default();
             let mut solver = solvers::create_cabs(cvrp, parameters, cabs_parameters);
-            io::run_solver_and_dump_solution_history(&mut solver, &args.history).unwrap()
+            io::run_solver_and_dump_solution_history(&mut solver, &args.common.history).unwrap()
         }
         SolverChoice::Astar => {
             println!("Preparing time: {}s", timer.get_elapsed_time());
             let mut solver = solvers::create_astar(cvrp, parameters);
-            io::run_solver_and_dump_solution_history(&mut solver, &args.history).unwrap()
+            io::run_solver_and_dump_solution_history(&mut solver, &args.common.history).unwrap()
         }
     };
     io::print_solution_statistics(&solution);
 
     if let Some(cost) = solution.cost {
-        let mut tours = vec![vec![]];
-
-        for transition in solution.transitions {
-            if transition >= instance.nodes.len() {
-                tours.push(vec![transition - instance.nodes.len()]);
-            } else {
-                tours.last_mut().unwrap().push(transition);
-            }
-        }
-
+        let tours = instance.transitions_to_tours(&solution.transitions);
         instance.print_solution(&tours);
 
         if instance.validate(&tours, cost) {
@@ -248,5 +396,9 @@ fn main() {
         } else {
             println!("The solution is invalid.");
         }
+
+        if let Some(filename) = &args.solution {
+            instance.write_solution(filename, &tours, cost).unwrap();
+        }
     }
 }
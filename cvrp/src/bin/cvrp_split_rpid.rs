@@ -0,0 +1,321 @@
+use clap::Parser;
+use cvrp::{CommonArgs, FleetSizeArgs, FleetSizeChoice, RoundedInstance, SolverChoice};
+use rpid::prelude::*;
+use rpid::{algorithms, io, solvers, timer::Timer};
+use std::cmp::{self, Ordering};
+use std::iter;
+
+#[cfg(not(target_env = "msvc"))]
+use tikv_jemallocator::Jemalloc;
+
+#[cfg(not(target_env = "msvc"))]
+#[global_allocator]
+static GLOBAL: Jemalloc = Jemalloc;
+
+#[derive(Debug, Parser)]
+struct Args {
+    #[command(flatten)]
+    common: CommonArgs,
+    #[command(flatten)]
+    vehicles: FleetSizeArgs,
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(i32).range(1..),
+        help = "Unit of partial deliveries (the maximal amount is delivered if omitted)"
+    )]
+    delivery_unit: Option<i32>,
+}
+
+struct SplitCvrp {
+    instance: RoundedInstance,
+    depot: usize,
+    n_vehicles: i32,
+    delivery_unit: Option<i32>,
+    min_to: Vec<i32>,
+    min_from: Vec<i32>,
+}
+
+impl SplitCvrp {
+    fn new(instance: RoundedInstance, delivery_unit: Option<i32>) -> Self {
+        // Multiple depots are rejected before creating the model.
+        let depot = instance.depots[0];
+        let n_vehicles = instance.get_total_vehicles() as i32;
+        let min_to = algorithms::take_column_wise_min_with_option(&instance.distances)
+            .map(|x| x.unwrap())
+            .collect::<Vec<_>>();
+        let min_from = algorithms::take_row_wise_min_with_option(&instance.distances)
+            .map(|x| x.unwrap())
+            .collect::<Vec<_>>();
+
+        Self {
+            instance,
+            depot,
+            n_vehicles,
+            delivery_unit,
+            min_to,
+            min_from,
+        }
+    }
+}
+
+struct SplitCvrpState {
+    remaining: Vec<i32>,
+    current: usize,
+    load: i32,
+    n_vehicles: i32,
+    length: i32,
+}
+
+impl SplitCvrp {
+    // Returns the amounts that can be delivered to a customer with `remaining` demand.
+    // The maximal amount is always included, and smaller ones are multiples of the delivery unit,
+    // which is positive.
+    fn get_amounts(&self, remaining: i32, load: i32) -> Vec<i32> {
+        let max_amount = cmp::min(remaining, self.instance.capacity - load);
+
+        if max_amount <= 0 {
+            return vec![];
+        }
+
+        if let Some(unit) = self.delivery_unit {
+            (unit..max_amount)
+                .step_by(unit as usize)
+                .chain(iter::once(max_amount))
+                .collect()
+        } else {
+            vec![max_amount]
+        }
+    }
+
+    fn check_feasibility(&self, state: &SplitCvrpState) -> bool {
+        let remaining_demand = state.remaining.iter().sum::<i32>();
+
+        (self.n_vehicles - state.n_vehicles + 1) * self.instance.capacity
+            >= state.load + remaining_demand
+    }
+}
+
+impl Dp for SplitCvrp {
+    type State = SplitCvrpState;
+    type CostType = i32;
+
+    fn get_target(&self) -> Self::State {
+        let mut remaining = self.instance.demands.clone();
+        remaining[self.depot] = 0;
+
+        SplitCvrpState {
+            remaining,
+            current: self.depot,
+            load: 0,
+            n_vehicles: 1,
+            length: 0,
+        }
+    }
+
+    fn get_successors(
+        &self,
+        state: &Self::State,
+    ) -> impl IntoIterator<Item = (Self::State, Self::CostType, usize)> {
+        let n = self.instance.nodes.len();
+        let depot = self.depot;
+        let mut successors = vec![];
+
+        for (next, &remaining) in state.remaining.iter().enumerate() {
+            if next == state.current || remaining == 0 {
+                continue;
+            }
+
+            let Some(distance) = self.instance.distances[state.current][next] else {
+                continue;
+            };
+            let Some(length) =
+                self.instance
+                    .extend_route_length(state.length, distance, next, depot)
+            else {
+                continue;
+            };
+
+            for amount in self.get_amounts(remaining, state.load) {
+                let mut remaining = state.remaining.clone();
+                remaining[next] -= amount;
+                let successor = SplitCvrpState {
+                    remaining,
+                    current: next,
+                    load: state.load + amount,
+                    n_vehicles: state.n_vehicles,
+                    length,
+                };
+
+                if self.check_feasibility(&successor) {
+                    successors.push((successor, distance, n * 2 * amount as usize + next));
+                }
+            }
+        }
+
+        // The route is empty only in the target state.
+        if state.current == depot || state.n_vehicles >= self.n_vehicles {
+            return successors;
+        }
+
+        let Some(distance_to_depot) = self.instance.distances[state.current][depot] else {
+            return successors;
+        };
+
+        for (next, &remaining) in state.remaining.iter().enumerate() {
+            if remaining == 0 {
+                continue;
+            }
+
+            let Some(distance_from_depot) = self.instance.distances[depot][next] else {
+                continue;
+            };
+            let Some(length) =
+                self.instance
+                    .extend_route_length(0, distance_from_depot, next, depot)
+            else {
+                continue;
+            };
+
+            for amount in self.get_amounts(remaining, 0) {
+                let mut remaining = state.remaining.clone();
+                remaining[next] -= amount;
+                let successor = SplitCvrpState {
+                    remaining,
+                    current: next,
+                    load: amount,
+                    n_vehicles: state.n_vehicles + 1,
+                    length,
+                };
+
+                if self.check_feasibility(&successor) {
+                    let weight = distance_to_depot + distance_from_depot;
+                    successors.push((successor, weight, n * (2 * amount as usize + 1) + next));
+                }
+            }
+        }
+
+        successors
+    }
+
+    fn get_base_cost(&self, state: &Self::State) -> Option<Self::CostType> {
+        if state.remaining.iter().all(|&d| d == 0) {
+            if state.current == self.depot {
+                Some(0)
+            } else {
+                self.instance.distances[state.current][self.depot]
+            }
+        } else {
+            None
+        }
+    }
+}
+
+impl Dominance for SplitCvrp {
+    type State = SplitCvrpState;
+    type Key = (Vec<i32>, usize);
+
+    fn get_key(&self, state: &Self::State) -> Self::Key {
+        (state.remaining.clone(), state.current)
+    }
+
+    fn compare(&self, a: &Self::State, b: &Self::State) -> Option<Ordering> {
+        if a.load == b.load && a.n_vehicles == b.n_vehicles && a.length == b.length {
+            Some(Ordering::Equal)
+        } else if a.load <= b.load && a.n_vehicles <= b.n_vehicles && a.length <= b.length {
+            Some(Ordering::Greater)
+        } else if a.load >= b.load && a.n_vehicles >= b.n_vehicles && a.length >= b.length {
+            Some(Ordering::Less)
+        } else {
+            None
+        }
+    }
+}
+
+impl Bound for SplitCvrp {
+    type State = SplitCvrpState;
+    type CostType = i32;
+
+    fn get_dual_bound(&self, state: &Self::State) -> Option<Self::CostType> {
+        // Each customer with remaining demand must be entered and left at least once more.
+        let customers =
+            state
+                .remaining
+                .iter()
+                .enumerate()
+                .filter_map(|(i, &d)| if d > 0 { Some(i) } else { None });
+        let bound_to =
+            customers.clone().map(|i| self.min_to[i]).sum::<i32>() + self.min_to[self.depot];
+        let bound_from =
+            customers.map(|i| self.min_from[i]).sum::<i32>() + self.min_from[state.current];
+
+        Some(cmp::max(bound_to, bound_from))
+    }
+}
+
+fn main() {
+    let timer = Timer::default();
+    let args = Args::parse();
+
+    let mut instance = RoundedInstance::load(
+        &args.common.input_file,
+        &args.vehicles.number,
+        args.vehicles.fleet_size.clone(),
+    )
+    .unwrap();
+
+    if instance.depots.len() > 1 {
+        println!("Use cvrp_rpid, cvrp_mst_rpid, or cvrp_dypdl for multiple depots");
+
+        return;
+    }
+
+    // The number of vehicles for unsplit deliveries is not valid when a demand exceeds the capacity.
+    match args.vehicles.fleet_size {
+        FleetSizeChoice::Fixed => {}
+        FleetSizeChoice::Minimum => {
+            instance.n_vehicles = vec![instance.compute_split_delivery_lower_bound()];
+            instance.max_total_vehicles = None;
+        }
+        FleetSizeChoice::Unlimited => {
+            instance.n_vehicles = vec![instance.compute_split_delivery_upper_bound()]
+        }
+    }
+
+    println!("Number of vehicles: {}", instance.get_total_vehicles());
+
+    if args.common.reduce_edges {
+        println!("Edge reduction is ignored since it assumes unsplit deliveries");
+    }
+
+    let cvrp = SplitCvrp::new(instance.clone(), args.delivery_unit);
+
+    let parameters = SearchParameters {
+        time_limit: Some(args.common.time_limit),
+        ..Default::default()
+    };
+    let solution = match args.common.solver {
+        SolverChoice::Cabs => {
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let cabs_parameters = CabsParameters::default();
+            let mut solver = solvers::create_cabs(cvrp, parameters, cabs_parameters);
+            io::run_solver_and_dump_solution_history(&mut solver, &args.common.history).unwrap()
+        }
+        SolverChoice::Astar => {
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let mut solver = solvers::create_astar(cvrp, parameters);
+            io::run_solver_and_dump_solution_history(&mut solver, &args.common.history).unwrap()
+        }
+    };
+    io::print_solution_statistics(&solution);
+
+    if let Some(cost) = solution.cost {
+        let tours = instance.transitions_to_split_tours(&solution.transitions);
+        instance.print_split_solution(&tours);
+
+        if instance.validate_split_delivery(&tours, cost) {
+            println!("The solution is valid.");
+        } else {
+            println!("The solution is invalid.");
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use clap::Parser;
+use cvrp::{FleetSizeArgs, RoundedInstance};
+
+#[derive(Debug, Parser)]
+struct Args {
+    #[arg(help = "Input file")]
+    input_file: String,
+    #[arg(help = "Solution file in the CVRPLIB format")]
+    solution_file: String,
+    #[command(flatten)]
+    vehicles: FleetSizeArgs,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let instance = RoundedInstance::load(
+        &args.input_file,
+        &args.vehicles.number,
+        args.vehicles.fleet_size,
+    )
+    .unwrap();
+    let (tours, cost) = instance.read_solution(&args.solution_file).unwrap();
+    println!("cost: {}", cost);
+    instance.print_solution(&tours);
+
+    if instance.validate(&tours, cost) {
+        println!("The solution is valid.");
+    } else {
+        println!("The solution is invalid.");
+    }
+}
@@ -1,18 +1,35 @@
 use clap::{Parser, ValueEnum};
+use regex::Regex;
+use std::cmp;
 use std::error::Error;
+use std::fs;
+use std::io::Write;
 use tsplib_parser::Instance;
 
+/// Route given by the depot and the sequence of customers.
+pub type Route = (usize, Vec<usize>);
+
+/// Route of the split-delivery model given by the sequence of customers and delivered amounts.
+pub type SplitRoute = Vec<(usize, i32)>;
+
 #[derive(Clone, Debug)]
 pub struct RoundedInstance {
-    pub n_vehicles: usize,
+    pub n_vehicles: Vec<usize>,
+    /// Cap on the total number of vehicles over all depots if it is smaller than the sum of `n_vehicles`.
+    pub max_total_vehicles: Option<usize>,
     pub nodes: Vec<usize>,
     pub demands: Vec<i32>,
-    pub depot: usize,
+    pub depots: Vec<usize>,
     pub capacity: i32,
     pub distances: Vec<Vec<Option<i32>>>,
+    pub max_route_length: Option<i32>,
+    pub service_times: Vec<i32>,
+    /// Difference between the id of a node and its number in solution files.
+    pub solution_id_offset: usize,
 }
 
 impl RoundedInstance {
+    /// Creates an instance with `n_vehicles` vehicles at each depot.
     pub fn new(instance: Instance, n_vehicles: usize) -> Result<Self, Box<dyn Error>> {
         let distances = instance.get_full_distance_matrix()?;
         let distances = distances
@@ -39,58 +56,514 @@ impl RoundedInstance {
         demands.sort_by_key(|(i, _)| *i);
         let nodes = demands.iter().map(|(i, _)| *i).collect::<Vec<_>>();
         let demands = demands.into_iter().map(|(_, d)| d).collect();
-        let depots = instance.depots.ok_or("Depot not defined")?;
+        let depots = instance
+            .depots
+            .ok_or("Depot not defined")?
+            .into_iter()
+            .map(|depot| nodes.iter().position(|&i| i == depot))
+            .collect::<Option<Vec<_>>>()
+            .ok_or("Depot not found")?;
 
-        if depots.len() != 1 {
-            return Err("Only one depot is allowed".into());
+        if depots.is_empty() {
+            return Err("Depot not defined".into());
         }
 
-        let depot = nodes
-            .iter()
-            .position(|&i| i == depots[0])
-            .ok_or("Depot not found")?;
         let capacity = instance.capacity.ok_or("Capacity not found")?;
+        let max_route_length = instance.distance.map(|d| d.round() as i32);
+        let service_time = instance.service_time.map_or(0, |s| s.round() as i32);
+        let service_times = (0..nodes.len())
+            .map(|i| if depots.contains(&i) { 0 } else { service_time })
+            .collect();
 
         Ok(Self {
-            n_vehicles,
+            n_vehicles: vec![n_vehicles; depots.len()],
+            max_total_vehicles: None,
+            nodes,
+            demands,
+            depots,
+            capacity,
+            distances,
+            max_route_length,
+            service_times,
+            solution_id_offset: 1,
+        })
+    }
+
+    /// Reads a multi-depot instance in the format of Cordeau et al.
+    ///
+    /// Returns the instance with no vehicles and the number of vehicles at each depot given by the header,
+    /// which is the same for all depots.
+    pub fn read_cordeau(content: &str) -> Result<(Self, usize), Box<dyn Error>> {
+        let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+        let mut next_line = || -> Result<Vec<f64>, Box<dyn Error>> {
+            let line = lines.next().ok_or("unexpected end of file")?;
+
+            Ok(line
+                .split_whitespace()
+                .map(|x| x.parse())
+                .collect::<Result<_, _>>()?)
+        };
+
+        let header = next_line()?;
+
+        if header.len() < 4 || header[0] != 2.0 {
+            return Err("Only MDVRP instances (type 2) are supported".into());
+        }
+
+        let n_vehicles = header[1] as usize;
+        let n_customers = header[2] as usize;
+        let n_depots = header[3] as usize;
+        let limits = (0..n_depots)
+            .map(|_| next_line())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if limits
+            .iter()
+            .any(|limit| limit.len() < 2 || limit[..2] != limits[0][..2])
+        {
+            return Err("Different route limits among depots are not supported".into());
+        }
+
+        let max_route_length = Some(limits[0][0].round() as i32).filter(|&d| d > 0);
+        let capacity = limits[0][1].round() as i32;
+
+        let n = n_customers + n_depots;
+        let rows = (0..n).map(|_| next_line()).collect::<Result<Vec<_>, _>>()?;
+
+        if rows.iter().any(|row| row.len() < 5) {
+            return Err("Invalid node description".into());
+        }
+
+        let nodes = rows.iter().map(|row| row[0] as usize).collect();
+        let demands = rows.iter().map(|row| row[4] as i32).collect();
+        let service_times = rows.iter().map(|row| row[3].round() as i32).collect();
+        let depots = (n_customers..n).collect();
+        let distances = rows
+            .iter()
+            .enumerate()
+            .map(|(i, a)| {
+                rows.iter()
+                    .enumerate()
+                    .map(|(j, b)| {
+                        if i == j {
+                            None
+                        } else {
+                            Some((a[1] - b[1]).hypot(a[2] - b[2]).round() as i32)
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let instance = Self {
+            n_vehicles: vec![0; n_depots],
+            max_total_vehicles: None,
             nodes,
             demands,
-            depot,
+            depots,
             capacity,
             distances,
+            max_route_length,
+            service_times,
+            solution_id_offset: 0,
+        };
+
+        Ok((instance, n_vehicles))
+    }
+
+    /// Loads an instance and determines the number of vehicles at each depot according to `fleet_size`.
+    ///
+    /// Both TSPLIB and the multi-depot format of Cordeau et al. are accepted.
+    /// With `FleetSizeChoice::Fixed`, the number of vehicles is taken from `vehicles`,
+    /// the `VEHICLES` keyword or the Cordeau header, or the file name following the `k<number>` convention,
+    /// in this order.
+    /// `vehicles` and the `VEHICLES` keyword give either one number for all depots
+    /// or one number per depot separated by commas.
+    /// With `FleetSizeChoice::Minimum`, the lower bound on the number of vehicles is used as the cap
+    /// on the total number over all depots, which can be used by any depot.
+    /// Giving `vehicles` with a fleet size other than `FleetSizeChoice::Fixed` is an error.
+    pub fn load(
+        filename: &str,
+        vehicles: &[usize],
+        fleet_size: FleetSizeChoice,
+    ) -> Result<Self, Box<dyn Error>> {
+        if !vehicles.is_empty() && !matches!(fleet_size, FleetSizeChoice::Fixed) {
+            return Err(format!(
+                "the number of vehicles is given but not used with fleet size {:?}",
+                fleet_size
+            )
+            .into());
+        }
+
+        let content = fs::read_to_string(filename)?;
+        let is_cordeau = content
+            .split_whitespace()
+            .next()
+            .is_some_and(|token| token.parse::<usize>().is_ok());
+
+        let (mut instance, file_vehicles) = if is_cordeau {
+            let (instance, n_vehicles) = Self::read_cordeau(&content)?;
+
+            (instance, Some(vec![n_vehicles]))
+        } else {
+            let mut keyword_vehicles = None;
+            let mut lines = Vec::new();
+
+            // The TSPLIB parser does not accept the VEHICLES keyword, so it is removed beforehand.
+            for line in content.lines() {
+                match line.split_once(':') {
+                    Some((key, value)) if key.trim() == "VEHICLES" => {
+                        keyword_vehicles = Some(
+                            value
+                                .split(',')
+                                .map(|n| n.trim().parse())
+                                .collect::<Result<Vec<_>, _>>()?,
+                        );
+                    }
+                    _ => lines.push(line),
+                }
+            }
+
+            let instance = Instance::parse(&lines.join("\n"))?;
+
+            (Self::new(instance, 0)?, keyword_vehicles)
+        };
+
+        let n_vehicles = match fleet_size {
+            FleetSizeChoice::Fixed => {
+                let filename = filename.split('/').next_back().unwrap_or(filename);
+                let re = Regex::new(r".+k(\d+).+").unwrap();
+
+                let n_vehicles = if vehicles.is_empty() {
+                    file_vehicles.or_else(|| Some(vec![re.captures(filename)?[1].parse().ok()?]))
+                } else {
+                    Some(vehicles.to_vec())
+                }
+                .ok_or("Number of vehicles not given by the option, the file, or the file name")?;
+
+                match n_vehicles[..] {
+                    [n] => vec![n; instance.depots.len()],
+                    _ if n_vehicles.len() == instance.depots.len() => n_vehicles,
+                    _ => {
+                        return Err(format!(
+                            "{} numbers of vehicles are given for {} depots",
+                            n_vehicles.len(),
+                            instance.depots.len()
+                        )
+                        .into())
+                    }
+                }
+            }
+            FleetSizeChoice::Minimum => {
+                let n_vehicles = instance.compute_bin_packing_lower_bound();
+                instance.max_total_vehicles = Some(n_vehicles);
+
+                vec![n_vehicles; instance.depots.len()]
+            }
+            FleetSizeChoice::Unlimited => {
+                vec![instance.nodes.len() - instance.depots.len(); instance.depots.len()]
+            }
+        };
+        instance.n_vehicles = n_vehicles;
+
+        Ok(instance)
+    }
+
+    /// Checks if the distance matrix is symmetric.
+    pub fn is_symmetric(&self) -> bool {
+        self.distances.iter().enumerate().all(|(i, row)| {
+            row.iter()
+                .enumerate()
+                .all(|(j, &d)| d == self.distances[j][i])
         })
     }
 
-    pub fn validate(&self, tours: &[Vec<usize>], cost: i32) -> bool {
-        if tours.len() > self.n_vehicles {
+    /// Returns the total number of vehicles.
+    pub fn get_total_vehicles(&self) -> usize {
+        let total = self.n_vehicles.iter().sum();
+
+        self.max_total_vehicles
+            .map_or(total, |max_total| cmp::min(total, max_total))
+    }
+
+    /// Computes the Martello and Toth lower bound (L2) on the number of vehicles
+    /// from the demands and the capacity.
+    pub fn compute_bin_packing_lower_bound(&self) -> usize {
+        let capacity = self.capacity;
+        let demands = self
+            .demands
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &d)| {
+                if self.depots.contains(&i) {
+                    None
+                } else {
+                    Some(d)
+                }
+            })
+            .collect::<Vec<_>>();
+        let total_demand = demands.iter().sum::<i32>();
+        let mut bound = cmp::max((total_demand + capacity - 1) / capacity, 1) as usize;
+
+        for &k in demands.iter().filter(|&&d| 2 * d <= capacity) {
+            let large = demands.iter().filter(|&&d| d > capacity - k).count();
+            let (medium, medium_demand) = demands
+                .iter()
+                .filter(|&&d| d <= capacity - k && 2 * d > capacity)
+                .fold((0, 0), |(count, sum), &d| (count + 1, sum + d));
+            let small_demand = demands
+                .iter()
+                .filter(|&&d| 2 * d <= capacity && d >= k)
+                .sum::<i32>();
+            let residual = medium as i32 * capacity - medium_demand;
+            let additional = cmp::max(small_demand - residual + capacity - 1, 0) / capacity;
+            bound = cmp::max(bound, large + medium + additional as usize);
+        }
+
+        bound
+    }
+
+    /// Computes the lower bound on the number of vehicles when deliveries can be split.
+    pub fn compute_split_delivery_lower_bound(&self) -> usize {
+        let total_demand = self.demands.iter().sum::<i32>();
+
+        cmp::max((total_demand + self.capacity - 1) / self.capacity, 1) as usize
+    }
+
+    /// Computes the number of vehicles sufficient to serve all customers when deliveries can be split.
+    pub fn compute_split_delivery_upper_bound(&self) -> usize {
+        self.demands
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !self.depots.contains(i))
+            .map(|(_, &d)| cmp::max((d + self.capacity - 1) / self.capacity, 1) as usize)
+            .sum()
+    }
+
+    /// Validates routes each of which starts and ends at a depot.
+    ///
+    /// Each route is given by the depot and the sequence of customers.
+    pub fn validate(&self, tours: &[Route], cost: i32) -> bool {
+        let n_vehicles = self.get_total_vehicles();
+
+        if tours.len() > n_vehicles {
             println!(
                 "Invalid number of vehicles {} > {}",
                 tours.len(),
-                self.n_vehicles
+                n_vehicles
             );
 
             return false;
         }
 
-        if tours.iter().map(|t| t.len()).sum::<usize>() != self.nodes.len() - 1 {
+        let mut n_used = vec![0; self.depots.len()];
+
+        for (i, (depot, _)) in tours.iter().enumerate() {
+            if let Some(k) = self.depots.iter().position(|d| d == depot) {
+                n_used[k] += 1;
+            } else {
+                println!("Invalid depot {} of route {}", depot, i);
+
+                return false;
+            }
+        }
+
+        for ((&depot, &n), &n_vehicles) in self
+            .depots
+            .iter()
+            .zip(n_used.iter())
+            .zip(self.n_vehicles.iter())
+        {
+            if n > n_vehicles {
+                println!(
+                    "Invalid number of vehicles at depot {} {} > {}",
+                    depot, n, n_vehicles
+                );
+
+                return false;
+            }
+        }
+
+        if !self.check_visits(tours.iter().map(|(_, t)| &t[..])) {
+            return false;
+        }
+
+        let mut recomputed_cost = 0;
+
+        for (depot, t) in tours {
+            if let Some(c) = self.evaluate_route(*depot, t, true) {
+                recomputed_cost += c;
+            } else {
+                return false;
+            }
+        }
+
+        if recomputed_cost != cost {
+            println!("Invalid cost {} != {}", recomputed_cost, cost);
+
+            return false;
+        }
+
+        true
+    }
+
+    /// Validates routes each of which is assigned to a vehicle type.
+    ///
+    /// Only one depot is assumed.
+    pub fn validate_with_fleet(
+        &self,
+        vehicle_types: &[VehicleType],
+        tours: &[(usize, Vec<usize>)],
+        cost: i32,
+    ) -> bool {
+        let mut n_used = vec![0; vehicle_types.len()];
+
+        for (i, (t, _)) in tours.iter().enumerate() {
+            if *t >= vehicle_types.len() {
+                println!("Invalid vehicle type {} of route {}", t, i);
+
+                return false;
+            }
+
+            n_used[*t] += 1;
+        }
+
+        for (t, (vehicle_type, &n)) in vehicle_types.iter().zip(n_used.iter()).enumerate() {
+            if n > vehicle_type.count {
+                println!(
+                    "Invalid number of vehicles of type {} {} > {}",
+                    t, n, vehicle_type.count
+                );
+
+                return false;
+            }
+        }
+
+        if !self.check_visits(tours.iter().map(|(_, t)| &t[..])) {
+            return false;
+        }
+
+        let mut recomputed_cost = 0;
+
+        for (t, route) in tours {
+            let vehicle_type = &vehicle_types[*t];
+
+            if let Some(c) = self.evaluate_route_with_vehicle(
+                self.depots[0],
+                route,
+                vehicle_type.capacity,
+                |distance| vehicle_type.compute_travel_cost(distance),
+                true,
+            ) {
+                recomputed_cost += vehicle_type.fixed_cost + c;
+            } else {
+                return false;
+            }
+        }
+
+        if recomputed_cost != cost {
+            println!("Invalid cost {} != {}", recomputed_cost, cost);
+
+            return false;
+        }
+
+        true
+    }
+
+    /// Validates routes of the split-delivery model.
+    ///
+    /// Only one depot is assumed.
+    /// The amounts delivered to each customer must sum up to its demand.
+    pub fn validate_split_delivery(&self, tours: &[SplitRoute], cost: i32) -> bool {
+        let n_vehicles = self.get_total_vehicles();
+
+        if tours.len() > n_vehicles {
             println!(
-                "Invalid number of nodes {} != {}",
-                tours.iter().map(|t| t.len()).sum::<usize>(),
-                self.nodes.len() - 1
+                "Invalid number of vehicles {} > {}",
+                tours.len(),
+                n_vehicles
             );
 
             return false;
         }
 
-        let mut visited_by = vec![None; self.nodes.len()];
+        let mut delivered = vec![0; self.nodes.len()];
         let mut recomputed_cost = 0;
 
-        for (i, t) in tours.iter().enumerate() {
-            let mut current = self.depot;
+        for (i, tour) in tours.iter().enumerate() {
             let mut load = 0;
 
+            for &(node, amount) in tour {
+                if node >= self.nodes.len() || self.depots.contains(&node) {
+                    println!("Invalid node {} visited by route {}", node, i);
+
+                    return false;
+                }
+
+                if amount <= 0 {
+                    println!("Invalid amount {} delivered to node {}", amount, node);
+
+                    return false;
+                }
+
+                delivered[node] += amount;
+                load += amount;
+            }
+
+            if load > self.capacity {
+                println!(
+                    "Vehicle load exceeded {} > {} by route {}",
+                    load, self.capacity, i
+                );
+
+                return false;
+            }
+
+            let route = tour.iter().map(|&(node, _)| node).collect::<Vec<_>>();
+
+            // The load is already checked with the delivered amounts.
+            if let Some(c) =
+                self.evaluate_route_with_vehicle(self.depots[0], &route, i32::MAX, |d| d, true)
+            {
+                recomputed_cost += c;
+            } else {
+                return false;
+            }
+        }
+
+        for (i, (&d, &demand)) in delivered.iter().zip(self.demands.iter()).enumerate() {
+            if !self.depots.contains(&i) && d != demand {
+                println!("Invalid amount delivered to node {} {} != {}", i, d, demand);
+
+                return false;
+            }
+        }
+
+        if recomputed_cost != cost {
+            println!("Invalid cost {} != {}", recomputed_cost, cost);
+
+            return false;
+        }
+
+        true
+    }
+
+    // Checks if each customer is visited exactly once.
+    fn check_visits<'a>(&self, tours: impl Iterator<Item = &'a [usize]> + Clone) -> bool {
+        let n_visits = tours.clone().map(|t| t.len()).sum::<usize>();
+        let n_customers = self.nodes.len() - self.depots.len();
+
+        if n_visits != n_customers {
+            println!("Invalid number of nodes {} != {}", n_visits, n_customers);
+
+            return false;
+        }
+
+        let mut visited_by = vec![None; self.nodes.len()];
+
+        for (i, t) in tours.enumerate() {
             for &node in t {
-                if node >= self.nodes.len() {
+                if node >= self.nodes.len() || self.depots.contains(&node) {
                     println!("Invalid node {} visited by route {}", node, i);
 
                     return false;
@@ -102,56 +575,425 @@ impl RoundedInstance {
                     return false;
                 }
 
-                if let Some(c) = self.distances[current][node] {
-                    recomputed_cost += c;
-                } else {
-                    println!("Invalid edge {} -> {}", current, node);
+                visited_by[node] = Some(i);
+            }
+        }
 
-                    return false;
+        true
+    }
+
+    /// Returns the cost of a route starting and ending at `depot`, or `None` if it is infeasible.
+    fn evaluate_route(&self, depot: usize, route: &[usize], verbose: bool) -> Option<i32> {
+        self.evaluate_route_with_vehicle(depot, route, self.capacity, |distance| distance, verbose)
+    }
+
+    // Returns the travel cost of a route by a vehicle with `capacity`,
+    // where `travel_cost` converts the distance of each edge into the cost.
+    fn evaluate_route_with_vehicle(
+        &self,
+        depot: usize,
+        route: &[usize],
+        capacity: i32,
+        travel_cost: impl Fn(i32) -> i32,
+        verbose: bool,
+    ) -> Option<i32> {
+        let mut current = depot;
+        let mut load = 0;
+        let mut cost = 0;
+        let mut length = 0;
+
+        for &node in route {
+            if let Some(c) = self.distances[current][node] {
+                cost += travel_cost(c);
+                length += c + self.service_times[node];
+            } else {
+                if verbose {
+                    println!("Invalid edge {} -> {}", current, node);
                 }
 
-                load += self.demands[node];
+                return None;
+            }
+
+            load += self.demands[node];
 
-                if load > self.capacity {
+            if load > capacity {
+                if verbose {
                     println!(
                         "Vehicle load exceeded {} > {} at node {}",
-                        load, self.capacity, node
+                        load, capacity, node
                     );
+                }
 
-                    return false;
+                return None;
+            }
+
+            current = node;
+        }
+
+        if current != depot {
+            if let Some(c) = self.distances[current][depot] {
+                cost += travel_cost(c);
+                length += c;
+            } else {
+                if verbose {
+                    println!("Invalid edge {} -> {}", current, depot);
                 }
 
-                visited_by[node] = Some(i);
-                current = node;
+                return None;
             }
+        }
 
-            if current != self.depot {
-                if let Some(c) = self.distances[current][self.depot] {
-                    recomputed_cost += c;
-                } else {
-                    println!("Invalid edge {} -> {}", current, self.depot);
+        if let Some(max_route_length) = self.max_route_length {
+            if length > max_route_length {
+                if verbose {
+                    println!("Route length exceeded {} > {}", length, max_route_length);
+                }
 
-                    return false;
+                return None;
+            }
+        }
+
+        Some(cost)
+    }
+
+    /// Improves routes by 2-opt and or-opt moves within a route
+    /// and relocate and exchange moves between routes, keeping the capacity satisfied.
+    ///
+    /// Each route keeps its depot.
+    /// Returns the improved cost and routes if an improvement is found.
+    pub fn improve_tours(&self, tours: &[Route]) -> Option<(i32, Vec<Route>)> {
+        let depots = tours.iter().map(|(depot, _)| *depot).collect::<Vec<_>>();
+        let mut routes = tours
+            .iter()
+            .map(|(_, route)| route.clone())
+            .collect::<Vec<_>>();
+        let mut costs = depots
+            .iter()
+            .zip(routes.iter())
+            .map(|(&depot, route)| self.evaluate_route(depot, route, false))
+            .collect::<Option<Vec<_>>>()?;
+        let initial_cost = costs.iter().sum::<i32>();
+
+        loop {
+            for ((&depot, route), cost) in
+                depots.iter().zip(routes.iter_mut()).zip(costs.iter_mut())
+            {
+                if let Some((improved_cost, improved_route)) =
+                    io_util::improve_sequence_by_local_search(route, |route| {
+                        self.evaluate_route(depot, route, false)
+                    })
+                {
+                    *route = improved_route;
+                    *cost = improved_cost;
                 }
             }
+
+            if !self.relocate(&depots, &mut routes, &mut costs)
+                && !self.exchange(&depots, &mut routes, &mut costs)
+            {
+                break;
+            }
         }
 
-        if recomputed_cost != cost {
-            println!("Invalid cost {} != {}", recomputed_cost, cost);
+        let cost = costs.iter().sum::<i32>();
 
-            return false;
+        if cost < initial_cost {
+            let tours = depots
+                .into_iter()
+                .zip(routes)
+                .filter(|(_, route)| !route.is_empty())
+                .collect();
+
+            Some((cost, tours))
+        } else {
+            None
         }
+    }
 
-        true
+    fn relocate(&self, depots: &[usize], routes: &mut [Vec<usize>], costs: &mut [i32]) -> bool {
+        for a in 0..routes.len() {
+            for i in 0..routes[a].len() {
+                let mut from = routes[a].clone();
+                let node = from.remove(i);
+
+                let Some(from_cost) = self.evaluate_route(depots[a], &from, false) else {
+                    continue;
+                };
+
+                for b in (0..routes.len()).filter(|&b| b != a) {
+                    for j in 0..=routes[b].len() {
+                        let mut to = routes[b].clone();
+                        to.insert(j, node);
+
+                        if let Some(to_cost) = self.evaluate_route(depots[b], &to, false) {
+                            if from_cost + to_cost < costs[a] + costs[b] {
+                                routes[a] = from;
+                                routes[b] = to;
+                                costs[a] = from_cost;
+                                costs[b] = to_cost;
+
+                                return true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    fn exchange(&self, depots: &[usize], routes: &mut [Vec<usize>], costs: &mut [i32]) -> bool {
+        for a in 0..routes.len() {
+            for b in a + 1..routes.len() {
+                for i in 0..routes[a].len() {
+                    for j in 0..routes[b].len() {
+                        let mut first = routes[a].clone();
+                        let mut second = routes[b].clone();
+                        std::mem::swap(&mut first[i], &mut second[j]);
+
+                        if let (Some(first_cost), Some(second_cost)) = (
+                            self.evaluate_route(depots[a], &first, false),
+                            self.evaluate_route(depots[b], &second, false),
+                        ) {
+                            if first_cost + second_cost < costs[a] + costs[b] {
+                                routes[a] = first;
+                                routes[b] = second;
+                                costs[a] = first_cost;
+                                costs[b] = second_cost;
+
+                                return true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Splits transitions of the RPID models into routes with depots.
+    ///
+    /// A transition `i >= n` visits customer `i % n` in a new route from the `(i / n - 1)`-th depot.
+    /// The first route starts from the first depot unless it is replaced by such a transition.
+    pub fn transitions_to_tours(&self, transitions: &[usize]) -> Vec<Route> {
+        let n = self.nodes.len();
+        let mut tours = vec![(self.depots[0], vec![])];
+
+        for &transition in transitions {
+            if transition >= n {
+                let depot = self.depots[transition / n - 1];
+
+                if tours.len() == 1 && tours[0].1.is_empty() {
+                    tours[0] = (depot, vec![transition % n]);
+                } else {
+                    tours.push((depot, vec![transition % n]));
+                }
+            } else {
+                tours.last_mut().unwrap().1.push(transition);
+            }
+        }
+
+        tours
+    }
+
+    /// Converts routes with depots into transitions of the RPID models.
+    pub fn tours_to_transitions(&self, tours: &[Route]) -> Vec<usize> {
+        let n = self.nodes.len();
+
+        tours
+            .iter()
+            .enumerate()
+            .flat_map(|(i, (depot, tour))| {
+                let k = self.depots.iter().position(|d| d == depot).unwrap();
+
+                tour.iter().enumerate().map(move |(j, &node)| {
+                    if j == 0 && (i > 0 || k > 0) {
+                        n * (k + 1) + node
+                    } else {
+                        node
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Splits transitions of the heterogeneous fleet model into routes with vehicle types.
+    ///
+    /// A transition `i >= n` starts a new route with vehicle type `i / n - 1` visiting customer `i % n`.
+    pub fn transitions_to_fleet_tours(&self, transitions: &[usize]) -> Vec<(usize, Vec<usize>)> {
+        let n = self.nodes.len();
+        let mut tours: Vec<(usize, Vec<usize>)> = vec![];
+
+        for &transition in transitions {
+            if transition >= n {
+                tours.push((transition / n - 1, vec![transition % n]));
+            } else if let Some((_, tour)) = tours.last_mut() {
+                tour.push(transition);
+            }
+        }
+
+        tours
     }
 
-    pub fn print_solution(&self, tours: &[Vec<usize>]) {
+    /// Splits transitions of the split-delivery model into routes.
+    ///
+    /// A transition `i` delivers amount `i / n / 2` to customer `i % n`,
+    /// in a new route if `i / n` is odd and in the current route otherwise.
+    pub fn transitions_to_split_tours(&self, transitions: &[usize]) -> Vec<SplitRoute> {
+        let n = self.nodes.len();
+        let mut tours = vec![vec![]];
+
+        for &transition in transitions {
+            let delivery = (transition % n, (transition / n / 2) as i32);
+
+            if (transition / n) % 2 == 1 {
+                tours.push(vec![delivery]);
+            } else {
+                tours.last_mut().unwrap().push(delivery);
+            }
+        }
+
+        tours
+    }
+
+    /// Writes routes in the CVRPLIB solution format.
+    ///
+    /// As in CVRPLIB, a node of a TSPLIB instance is written as its id minus one,
+    /// and a node of a Cordeau instance is written as its id.
+    /// With multiple depots, the depot of each route is written after the route number.
+    pub fn write_solution(
+        &self,
+        filename: &str,
+        tours: &[Route],
+        cost: i32,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut file = fs::File::create(filename)?;
+
+        for (i, (depot, tour)) in tours.iter().enumerate() {
+            let tour = tour
+                .iter()
+                .map(|&j| (self.nodes[j] - self.solution_id_offset).to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            if self.depots.len() > 1 {
+                writeln!(
+                    file,
+                    "Route #{} (depot {}): {}",
+                    i + 1,
+                    self.nodes[*depot] - self.solution_id_offset,
+                    tour
+                )?;
+            } else {
+                writeln!(file, "Route #{}: {}", i + 1, tour)?;
+            }
+        }
+
+        writeln!(file, "Cost {}", cost)?;
+
+        Ok(())
+    }
+
+    /// Reads routes and the cost from a file in the CVRPLIB solution format.
+    ///
+    /// Nodes are mapped back to internal indices as in `write_solution`.
+    pub fn read_solution(&self, filename: &str) -> Result<(Vec<Route>, i32), Box<dyn Error>> {
+        let file = fs::read_to_string(filename)?;
+        let to_index = |number: &str| -> Result<usize, Box<dyn Error>> {
+            let id = number.parse::<usize>()? + self.solution_id_offset;
+
+            Ok(self
+                .nodes
+                .iter()
+                .position(|&i| i == id)
+                .ok_or(format!("Node {} not found", number))?)
+        };
+        let mut tours = vec![];
+        let mut cost = None;
+
+        for line in file.lines().map(|line| line.trim()) {
+            if let Some(route) = line.strip_prefix("Route #") {
+                let (header, customers) = route.split_once(':').ok_or("Invalid route")?;
+                let depot = match header.split_once("(depot") {
+                    Some((_, depot)) => to_index(depot.trim_end_matches(')').trim())?,
+                    None => self.depots[0],
+                };
+                let customers = customers
+                    .split_whitespace()
+                    .map(to_index)
+                    .collect::<Result<Vec<_>, _>>()?;
+                tours.push((depot, customers));
+            } else if let Some(c) = line.strip_prefix("Cost") {
+                cost = Some(c.trim().parse::<f64>()?.round() as i32);
+            }
+        }
+
+        Ok((tours, cost.ok_or("Cost not found")?))
+    }
+
+    pub fn print_solution(&self, tours: &[Route]) {
+        for (i, (depot, tour)) in tours.iter().enumerate() {
+            let tour = tour
+                .iter()
+                .map(|&j| j.to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            if self.depots.len() > 1 {
+                println!("Route {} (depot {}): {}", i + 1, depot, tour);
+            } else {
+                println!("Route {}: {}", i + 1, tour);
+            }
+        }
+    }
+
+    /// Returns the length of a route after moving to `next` by `distance` and serving `next`,
+    /// or `None` if the vehicle can no longer return to `depot` within the route length limit.
+    ///
+    /// The length is always zero if there is no limit.
+    pub fn extend_route_length(
+        &self,
+        length: i32,
+        distance: i32,
+        next: usize,
+        depot: usize,
+    ) -> Option<i32> {
+        if let Some(max_route_length) = self.max_route_length {
+            let length = length + distance + self.service_times[next];
+
+            if length + self.distances[next][depot]? <= max_route_length {
+                Some(length)
+            } else {
+                None
+            }
+        } else {
+            Some(0)
+        }
+    }
+
+    pub fn print_fleet_solution(&self, tours: &[(usize, Vec<usize>)]) {
+        for (i, (t, tour)) in tours.iter().enumerate() {
+            println!(
+                "Route {} (type {}): {}",
+                i + 1,
+                t,
+                tour.iter()
+                    .map(|&j| j.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
+        }
+    }
+
+    pub fn print_split_solution(&self, tours: &[SplitRoute]) {
         for (i, tour) in tours.iter().enumerate() {
             println!(
                 "Route {}: {}",
                 i + 1,
                 tour.iter()
-                    .map(|&j| j.to_string())
+                    .map(|(j, amount)| format!("{}({})", j, amount))
                     .collect::<Vec<_>>()
                     .join(" ")
             );
@@ -159,14 +1001,85 @@ impl RoundedInstance {
     }
 
     pub fn reduce_edges(&mut self) {
-        for (i, row) in self.distances.iter_mut().enumerate() {
-            for (j, d) in row.iter_mut().enumerate() {
-                if d.is_some() && self.demands[i] + self.demands[j] > self.capacity {
-                    *d = None;
+        let n = self.nodes.len();
+
+        for i in 0..n {
+            for j in 0..n {
+                if self.distances[i][j].is_some()
+                    && (self.demands[i] + self.demands[j] > self.capacity
+                        || !self.satisfies_route_length(i, j))
+                {
+                    self.distances[i][j] = None;
                 }
             }
         }
     }
+
+    // Checks if a route visiting `i` and then `j` can satisfy the route length limit.
+    fn satisfies_route_length(&self, i: usize, j: usize) -> bool {
+        let Some(max_route_length) = self.max_route_length else {
+            return true;
+        };
+
+        if self.depots.contains(&i) || self.depots.contains(&j) {
+            return true;
+        }
+
+        self.depots.iter().any(|&depot| {
+            match (
+                self.distances[depot][i],
+                self.distances[i][j],
+                self.distances[j][depot],
+            ) {
+                (Some(from_depot), Some(distance), Some(to_depot)) => {
+                    let service_time = self.service_times[i] + self.service_times[j];
+
+                    from_depot + distance + to_depot + service_time <= max_route_length
+                }
+                _ => true,
+            }
+        })
+    }
+}
+
+/// Vehicle type of a heterogeneous fleet.
+#[derive(Clone, Debug)]
+pub struct VehicleType {
+    pub count: usize,
+    pub capacity: i32,
+    pub fixed_cost: i32,
+    pub distance_cost: f64,
+}
+
+impl VehicleType {
+    /// Reads vehicle types from a file.
+    ///
+    /// The file starts with the number of vehicle types,
+    /// followed by the count, capacity, fixed cost, and cost per distance of each type.
+    pub fn read_fleet_from_file(filename: &str) -> Result<Vec<Self>, Box<dyn Error>> {
+        let file = fs::read_to_string(filename)?;
+        let mut digits = file.split_whitespace();
+        let mut next = || digits.next().ok_or("unexpected end of file");
+
+        let m = next()?.parse::<usize>()?;
+        let mut vehicle_types = Vec::with_capacity(m);
+
+        for _ in 0..m {
+            vehicle_types.push(Self {
+                count: next()?.parse()?,
+                capacity: next()?.parse()?,
+                fixed_cost: next()?.parse()?,
+                distance_cost: next()?.parse()?,
+            });
+        }
+
+        Ok(vehicle_types)
+    }
+
+    /// Returns the cost to travel an edge, rounded to the nearest integer.
+    pub fn compute_travel_cost(&self, distance: i32) -> i32 {
+        (f64::from(distance) * self.distance_cost).round() as i32
+    }
 }
 
 #[derive(Debug, Clone, ValueEnum)]
@@ -175,8 +1088,22 @@ pub enum SolverChoice {
     Astar,
 }
 
-#[derive(Debug, Parser)]
-pub struct Args {
+#[derive(Debug, Clone, ValueEnum)]
+pub enum BoundChoice {
+    Assignment,
+    MinEdge,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum FleetSizeChoice {
+    Fixed,
+    Minimum,
+    Unlimited,
+}
+
+/// Options shared by all the solvers.
+#[derive(Debug, clap::Args)]
+pub struct CommonArgs {
     #[arg(help = "Input file")]
     pub input_file: String,
     #[arg(short, long, value_enum, default_value_t = SolverChoice::Cabs, help = "Solver")]
@@ -188,3 +1115,163 @@ pub struct Args {
     #[arg(short, long, action, help = "Performs edge reduction")]
     pub reduce_edges: bool,
 }
+
+/// Options to determine the number of vehicles.
+#[derive(Debug, clap::Args)]
+pub struct FleetSizeArgs {
+    #[arg(
+        long = "vehicles",
+        value_delimiter = ',',
+        help = "Number of vehicles with --fleet-size fixed, one for all depots or one per depot separated by commas (read from the VEHICLES keyword or the file name if omitted)"
+    )]
+    pub number: Vec<usize>,
+    #[arg(long, value_enum, default_value_t = FleetSizeChoice::Fixed, help = "How to determine the number of vehicles (the minimum is a lower bound on the total number over all depots and may be infeasible)")]
+    pub fleet_size: FleetSizeChoice,
+}
+
+/// Options of the models with a homogeneous fleet and unsplit deliveries.
+///
+/// The models with other options define their own arguments.
+#[derive(Debug, Parser)]
+pub struct Args {
+    #[command(flatten)]
+    pub common: CommonArgs,
+    #[command(flatten)]
+    pub vehicles: FleetSizeArgs,
+    #[arg(
+        long,
+        action,
+        help = "Improve new solutions found by CABS with local search"
+    )]
+    pub local_search: bool,
+    #[arg(long, help = "File to save the solution in the CVRPLIB format")]
+    pub solution: Option<String>,
+    #[arg(
+        long,
+        action,
+        help = "Break route symmetry by requiring each route to visit the lowest-indexed customer unvisited at its start"
+    )]
+    pub break_symmetry: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two depots with ids 4 and 5 after three customers.
+    const CORDEAU_INSTANCE: &str = "\
+2 2 3 2
+0 10
+0 10
+1 0 10 0 4
+2 10 0 0 5
+3 10 10 0 3
+4 0 0 0 0
+5 20 20 0 0
+";
+
+    // Two depots with ids 1 and 4 and two customers.
+    const TSPLIB_INSTANCE: &str = "\
+NAME : test
+TYPE : CVRP
+DIMENSION : 4
+VEHICLES : 1, 2
+CAPACITY : 10
+EDGE_WEIGHT_TYPE : EUC_2D
+NODE_COORD_SECTION
+1 0 0
+2 0 10
+3 10 0
+4 20 20
+DEMAND_SECTION
+1 0
+2 4
+3 5
+4 0
+DEPOT_SECTION
+1
+4
+-1
+EOF
+";
+
+    fn write_file(name: &str, content: &str) -> String {
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, content).unwrap();
+
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn load_assigns_vehicles_to_depots() {
+        let filename = write_file("cvrp_load_vehicles.txt", CORDEAU_INSTANCE);
+
+        let instance = RoundedInstance::load(&filename, &[], FleetSizeChoice::Fixed).unwrap();
+        assert_eq!(instance.depots, vec![3, 4]);
+        assert_eq!(instance.n_vehicles, vec![2, 2]);
+
+        let instance = RoundedInstance::load(&filename, &[3], FleetSizeChoice::Fixed).unwrap();
+        assert_eq!(instance.n_vehicles, vec![3, 3]);
+
+        let instance = RoundedInstance::load(&filename, &[1, 3], FleetSizeChoice::Fixed).unwrap();
+        assert_eq!(instance.n_vehicles, vec![1, 3]);
+
+        assert!(RoundedInstance::load(&filename, &[1, 2, 3], FleetSizeChoice::Fixed).is_err());
+        assert!(RoundedInstance::load(&filename, &[1], FleetSizeChoice::Unlimited).is_err());
+    }
+
+    #[test]
+    fn load_caps_total_vehicles_with_minimum_fleet_size() {
+        let filename = write_file("cvrp_load_minimum.txt", CORDEAU_INSTANCE);
+
+        let instance = RoundedInstance::load(&filename, &[], FleetSizeChoice::Minimum).unwrap();
+        assert_eq!(instance.n_vehicles, vec![2, 2]);
+        assert_eq!(instance.get_total_vehicles(), 2);
+
+        let evaluate = |routes: &[Route]| {
+            routes
+                .iter()
+                .map(|(depot, route)| instance.evaluate_route(*depot, route, false).unwrap())
+                .sum()
+        };
+        // Each depot can use two vehicles, but only two in total.
+        let routes = [(3, vec![0]), (4, vec![1]), (4, vec![2])];
+        assert!(!instance.validate(&routes, evaluate(&routes)));
+        let routes = [(3, vec![0]), (4, vec![1, 2])];
+        assert!(instance.validate(&routes, evaluate(&routes)));
+    }
+
+    #[test]
+    fn write_solution_and_read_solution_round_trip() {
+        let filename = write_file("cvrp_round_trip.txt", CORDEAU_INSTANCE);
+        let instance = RoundedInstance::load(&filename, &[], FleetSizeChoice::Fixed).unwrap();
+        let tours = vec![(4, vec![2, 0]), (3, vec![1])];
+        let solution = std::env::temp_dir().join("cvrp_round_trip.sol");
+        let solution = solution.to_str().unwrap();
+
+        instance.write_solution(solution, &tours, 42).unwrap();
+        // Cordeau ids are written as they are.
+        let content = fs::read_to_string(solution).unwrap();
+        assert!(content.starts_with("Route #1 (depot 5): 3 1\n"));
+        assert_eq!(instance.read_solution(solution).unwrap(), (tours, 42));
+
+        let filename = write_file("cvrp_round_trip.vrp", TSPLIB_INSTANCE);
+        let instance = RoundedInstance::load(&filename, &[], FleetSizeChoice::Fixed).unwrap();
+        let tours = vec![(0, vec![2]), (3, vec![1])];
+
+        instance.write_solution(solution, &tours, 42).unwrap();
+        // TSPLIB ids are written minus one as in CVRPLIB.
+        let content = fs::read_to_string(solution).unwrap();
+        assert!(content.starts_with("Route #1 (depot 0): 2\n"));
+        assert_eq!(instance.read_solution(solution).unwrap(), (tours, 42));
+    }
+
+    #[test]
+    fn load_reads_vehicles_per_depot_from_keyword() {
+        let filename = write_file("cvrp_load_keyword.vrp", TSPLIB_INSTANCE);
+
+        let instance = RoundedInstance::load(&filename, &[], FleetSizeChoice::Fixed).unwrap();
+        assert_eq!(instance.depots, vec![0, 3]);
+        assert_eq!(instance.n_vehicles, vec![1, 2]);
+    }
+}
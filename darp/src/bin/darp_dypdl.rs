@@ -0,0 +1,334 @@
+use clap::Parser;
+use darp::{Args, Instance, RoundedInstance, SolverChoice};
+use dypdl::prelude::*;
+use dypdl_heuristic_search::{
+    create_caasdy, create_dual_bound_cabs, BeamSearchParameters, CabsParameters, FEvaluatorType,
+    Parameters,
+};
+use rpid::{algorithms, timer::Timer};
+use std::rc::Rc;
+
+#[cfg(not(target_env = "msvc"))]
+use tikv_jemallocator::Jemalloc;
+
+#[cfg(not(target_env = "msvc"))]
+#[global_allocator]
+static GLOBAL: Jemalloc = Jemalloc;
+
+fn main() {
+    let timer = Timer::default();
+    let args = Args::parse();
+
+    let instance = Instance::read_from_file(&args.input_file).unwrap();
+    let mut instance = RoundedInstance::new(instance, args.round_to);
+
+    if let Some(vehicles) = args.vehicles {
+        instance.n_vehicles = vehicles;
+    }
+
+    println!("Number of vehicles: {}", instance.n_vehicles);
+
+    let mut model = Model::default();
+
+    let n_nodes = instance.vertices.len();
+    let n = instance.n_requests;
+    let node = model.add_object_type("node", n_nodes).unwrap();
+
+    let unvisited = (1..n_nodes).collect::<Vec<_>>();
+    let unvisited = model.create_set(node, &unvisited).unwrap();
+    let unvisited = model
+        .add_set_variable("unvisited", node, unvisited)
+        .unwrap();
+    let current = model.add_element_variable("current", node, 0).unwrap();
+    // The distances in the temporal network of the current route, which are larger if looser.
+    let network = instance.create_route_network();
+    let from_current = network
+        .from_current
+        .iter()
+        .enumerate()
+        .map(|(u, &d)| {
+            model
+                .add_integer_resource_variable(format!("from_current{}", u), false, d)
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    let from_origin = network
+        .from_origin
+        .iter()
+        .enumerate()
+        .map(|(u, &d)| {
+            model
+                .add_integer_resource_variable(format!("from_origin{}", u), false, d)
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    let load = model
+        .add_integer_resource_variable("load", true, 0)
+        .unwrap();
+    let n_vehicles = model
+        .add_integer_resource_variable("n_vehicles", true, 1)
+        .unwrap();
+    let distances = model
+        .add_table_2d("distances", instance.distances.clone())
+        .unwrap();
+    let travel_times = (0..n_nodes)
+        .map(|i| {
+            (0..n_nodes)
+                .map(|j| instance.get_travel_time(i, j))
+                .collect()
+        })
+        .collect::<Vec<Vec<_>>>();
+    let c_star = algorithms::compute_pairwise_shortest_path_costs(&travel_times);
+    let travel_times = model.add_table_2d("travel_times", travel_times).unwrap();
+    let c_star = model.add_table_2d("c_star", c_star).unwrap();
+
+    let return_time = travel_times.element(current, 0);
+    let can_return_to_depot = Condition::comparison_i(
+        ComparisonOperator::Le,
+        return_time.clone() - from_current[0],
+        instance.closing[0],
+    ) & Condition::comparison_i(
+        ComparisonOperator::Le,
+        return_time - from_current[1],
+        instance.max_route_duration,
+    );
+
+    for next in 1..n_nodes {
+        let mut visit = Transition::new(format!("{}", next));
+        visit.set_cost(distances.element(current, next) + IntegerExpression::Cost);
+
+        // The service at `next` starts after the arrival from `current` and after its opening.
+        let travel_time = travel_times.element(current, next);
+        let from_next = |u: usize| {
+            IntegerExpression::min(
+                from_current[u] - travel_time.clone(),
+                from_origin[u] - instance.opening[next],
+            )
+        };
+        let latest = if next <= n {
+            IntegerExpression::from(instance.closing[next])
+        } else {
+            let i = next - n;
+
+            IntegerExpression::min(
+                IntegerExpression::from(instance.closing[next]),
+                from_origin[1 + i] + instance.service_times[i] + instance.max_ride_time,
+            )
+        };
+
+        visit.add_effect(unvisited, unvisited.remove(next)).unwrap();
+        visit.add_effect(current, next).unwrap();
+        visit.add_effect(from_current[0], from_next(0)).unwrap();
+
+        for u in 1..=n + 1 {
+            let updated_from_origin = IntegerExpression::min(
+                IntegerExpression::from(from_origin[u]),
+                latest.clone() + from_next(u),
+            );
+
+            if u == 1 {
+                visit.add_effect(from_current[u], from_next(u)).unwrap();
+                visit
+                    .add_effect(from_origin[u], updated_from_origin)
+                    .unwrap();
+            } else if u - 1 == next {
+                visit.add_effect(from_current[u], 0).unwrap();
+                visit.add_effect(from_origin[u], latest.clone()).unwrap();
+            } else if u - 1 + n == next {
+                visit.add_effect(from_current[u], 0).unwrap();
+                visit.add_effect(from_origin[u], 0).unwrap();
+            } else {
+                // The pickups not on board do not constrain the rest of the route.
+                let on_board = !unvisited.contains(u - 1) & unvisited.contains(u - 1 + n);
+                let updated_from_current: IntegerExpression =
+                    on_board.clone().if_then_else(from_next(u), 0);
+                visit
+                    .add_effect(from_current[u], updated_from_current)
+                    .unwrap();
+                let updated_from_origin: IntegerExpression =
+                    on_board.if_then_else(updated_from_origin, 0);
+                visit
+                    .add_effect(from_origin[u], updated_from_origin)
+                    .unwrap();
+            }
+        }
+
+        let new_load = load + instance.demands[next];
+        visit.add_effect(load, new_load.clone()).unwrap();
+
+        visit.add_precondition(unvisited.contains(next));
+        visit.add_precondition(Condition::comparison_i(
+            ComparisonOperator::Le,
+            new_load,
+            instance.capacity,
+        ));
+        visit.add_precondition(Condition::comparison_i(
+            ComparisonOperator::Le,
+            -from_next(0),
+            instance.closing[next],
+        ));
+
+        if next > n {
+            let i = next - n;
+            visit.add_precondition(!unvisited.contains(i));
+            visit.add_precondition(Condition::comparison_i(
+                ComparisonOperator::Le,
+                -from_next(1 + i) - instance.service_times[i],
+                instance.max_ride_time,
+            ));
+        }
+
+        model.add_forward_transition(visit).unwrap();
+    }
+
+    // A new route starts only after all requests on board are delivered.
+    for next in 1..=n {
+        if instance.demands[next] > instance.capacity {
+            continue;
+        }
+
+        let Some(network) =
+            instance.extend_route_network(&instance.create_route_network(), 0, next, false)
+        else {
+            continue;
+        };
+
+        let mut new_route = Transition::new(format!("{}", n_nodes + next));
+        new_route.set_cost(
+            distances.element(current, 0) + instance.distances[0][next] + IntegerExpression::Cost,
+        );
+
+        new_route
+            .add_effect(unvisited, unvisited.remove(next))
+            .unwrap();
+        new_route.add_effect(current, next).unwrap();
+
+        for (&variable, &d) in from_current.iter().zip(network.from_current.iter()) {
+            new_route.add_effect(variable, d).unwrap();
+        }
+
+        for (&variable, &d) in from_origin.iter().zip(network.from_origin.iter()) {
+            new_route.add_effect(variable, d).unwrap();
+        }
+
+        new_route.add_effect(load, instance.demands[next]).unwrap();
+        new_route.add_effect(n_vehicles, n_vehicles + 1).unwrap();
+
+        new_route.add_precondition(unvisited.contains(next));
+        new_route.add_precondition(Condition::comparison_e(ComparisonOperator::Ne, current, 0));
+        new_route.add_precondition(Condition::comparison_i(ComparisonOperator::Eq, load, 0));
+        new_route.add_precondition(Condition::comparison_i(
+            ComparisonOperator::Lt,
+            n_vehicles,
+            instance.n_vehicles as i32,
+        ));
+        new_route.add_precondition(can_return_to_depot.clone());
+
+        model.add_forward_transition(new_route).unwrap();
+    }
+
+    model
+        .add_base_case_with_cost(
+            vec![unvisited.is_empty(), can_return_to_depot],
+            distances.element(current, 0),
+        )
+        .unwrap();
+
+    for i in 1..=n {
+        // The requests on board must be delivered in the current route.
+        let delay = c_star.element(current, n + i);
+        let on_time = Condition::comparison_i(
+            ComparisonOperator::Le,
+            delay.clone() - from_current[0],
+            instance.closing[n + i],
+        ) & Condition::comparison_i(
+            ComparisonOperator::Le,
+            delay - from_current[1 + i] - instance.service_times[i],
+            instance.max_ride_time,
+        );
+        model
+            .add_state_constraint(unvisited.contains(i) | !unvisited.contains(n + i) | on_time)
+            .unwrap();
+    }
+
+    for j in 1..n_nodes {
+        // All the remaining nodes must be visited in the current route if no vehicle is left.
+        let on_time = Condition::comparison_i(
+            ComparisonOperator::Le,
+            c_star.element(current, j) - from_current[0],
+            instance.closing[j],
+        );
+        let vehicle_left = Condition::comparison_i(
+            ComparisonOperator::Lt,
+            n_vehicles,
+            instance.n_vehicles as i32,
+        );
+        model
+            .add_state_constraint(vehicle_left | !unvisited.contains(j) | on_time)
+            .unwrap();
+    }
+
+    let min_to = algorithms::take_column_wise_min_without_diagonal(&instance.distances)
+        .map(|x| x.unwrap())
+        .collect::<Vec<_>>();
+    let min_to = model.add_table_1d("min_to", min_to).unwrap();
+    model
+        .add_dual_bound(min_to.sum(unvisited) + min_to.element(0))
+        .unwrap();
+
+    let min_from = algorithms::take_row_wise_min_without_diagonal(&instance.distances)
+        .map(|x| x.unwrap())
+        .collect::<Vec<_>>();
+    let min_from = model.add_table_1d("min_from", min_from).unwrap();
+    model
+        .add_dual_bound(min_from.sum(unvisited) + min_from.element(current))
+        .unwrap();
+
+    let model = Rc::new(model);
+
+    let parameters = Parameters::<i32> {
+        time_limit: Some(args.time_limit),
+        ..Default::default()
+    };
+
+    let mut solver = match args.solver {
+        SolverChoice::Cabs => {
+            let beam_search_parameters = BeamSearchParameters {
+                parameters,
+                ..Default::default()
+            };
+            let parameters = CabsParameters {
+                beam_search_parameters,
+                ..Default::default()
+            };
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+
+            create_dual_bound_cabs(model, parameters, FEvaluatorType::Plus)
+        }
+        SolverChoice::Astar => {
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+
+            create_caasdy(model, parameters, FEvaluatorType::Plus)
+        }
+    };
+
+    let solution =
+        io_util::run_solver_and_dump_solution_history(&mut solver, &args.history).unwrap();
+    io_util::print_solution_statistics(&solution);
+
+    if let Some(cost) = solution.cost {
+        let transitions = solution
+            .transitions
+            .iter()
+            .map(|t| t.get_full_name().parse::<usize>().unwrap())
+            .collect::<Vec<_>>();
+        let routes = instance.transitions_to_routes(&transitions);
+        instance.print_solution(&routes);
+
+        if instance.validate(&routes, cost) {
+            println!("The solution is valid.");
+        } else {
+            println!("The solution is invalid.");
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use clap::Parser;
+use darp::{Args, Instance, RoundedInstance, RouteNetwork, SolverChoice};
+use fixedbitset::FixedBitSet;
+use rpid::prelude::*;
+use rpid::{algorithms, io, solvers, timer::Timer};
+use std::cmp::{self, Ordering};
+
+#[cfg(not(target_env = "msvc"))]
+use tikv_jemallocator::Jemalloc;
+
+#[cfg(not(target_env = "msvc"))]
+#[global_allocator]
+static GLOBAL: Jemalloc = Jemalloc;
+
+struct Darp {
+    instance: RoundedInstance,
+    n_vehicles: i32,
+    c_star: Vec<Vec<i32>>,
+    min_to: Vec<i32>,
+    min_from: Vec<i32>,
+}
+
+impl From<RoundedInstance> for Darp {
+    fn from(instance: RoundedInstance) -> Self {
+        let n = instance.vertices.len();
+        let travel_times = (0..n)
+            .map(|i| (0..n).map(|j| instance.get_travel_time(i, j)).collect())
+            .collect::<Vec<_>>();
+        let c_star = algorithms::compute_pairwise_shortest_path_costs(&travel_times);
+        let min_to = algorithms::take_column_wise_min_without_diagonal(&instance.distances)
+            .map(|x| x.unwrap())
+            .collect();
+        let min_from = algorithms::take_row_wise_min_without_diagonal(&instance.distances)
+            .map(|x| x.unwrap())
+            .collect();
+
+        Self {
+            n_vehicles: instance.n_vehicles as i32,
+            instance,
+            c_star,
+            min_to,
+            min_from,
+        }
+    }
+}
+
+struct DarpState {
+    unvisited: FixedBitSet,
+    current: usize,
+    network: RouteNetwork,
+    n_vehicles: i32,
+}
+
+impl Darp {
+    // A request is on board if its pickup is visited but its delivery is not.
+    fn get_load(&self, state: &DarpState) -> i32 {
+        let n = self.instance.n_requests;
+
+        (1..=n)
+            .filter(|&i| state.network.is_on_board(i))
+            .map(|i| self.instance.demands[i])
+            .sum()
+    }
+
+    fn visit(&self, state: &DarpState, next: usize, new_route: bool) -> Option<DarpState> {
+        let (current, network, n_vehicles) = if new_route {
+            (
+                0,
+                self.instance.create_route_network(),
+                state.n_vehicles + 1,
+            )
+        } else {
+            (state.current, state.network.clone(), state.n_vehicles)
+        };
+        let network = self
+            .instance
+            .extend_route_network(&network, current, next, false)?;
+        let mut unvisited = state.unvisited.clone();
+        unvisited.remove(next);
+
+        let successor = DarpState {
+            unvisited,
+            current: next,
+            network,
+            n_vehicles,
+        };
+
+        if self.check_feasibility(&successor) {
+            Some(successor)
+        } else {
+            None
+        }
+    }
+
+    // Since a node `j` is visited at least `c_star[current][j]` after the current node,
+    // the time constraints between them and the earlier nodes must be satisfied with that delay.
+    fn check_feasibility(&self, state: &DarpState) -> bool {
+        let n = self.instance.n_requests;
+        let network = &state.network;
+        let delay_to = |j: usize| self.c_star[state.current][j];
+
+        if delay_to(0) - network.from_current[0] > self.instance.closing[0]
+            || delay_to(0) - network.from_current[1] > self.instance.max_route_duration
+        {
+            return false;
+        }
+
+        // The requests on board must be delivered in the current route.
+        let on_time = (1..=n).filter(|&i| network.is_on_board(i)).all(|i| {
+            delay_to(n + i) - network.from_current[0] <= self.instance.closing[n + i]
+                && delay_to(n + i) - network.from_current[1 + i] - self.instance.service_times[i]
+                    <= self.instance.max_ride_time
+        });
+
+        // All the remaining nodes must be visited in the current route if no vehicle is left.
+        on_time
+            && (state.n_vehicles < self.n_vehicles
+                || state
+                    .unvisited
+                    .ones()
+                    .all(|j| delay_to(j) - network.from_current[0] <= self.instance.closing[j]))
+    }
+}
+
+impl Dp for Darp {
+    type State = DarpState;
+    type CostType = i32;
+
+    fn get_target(&self) -> Self::State {
+        let mut unvisited = FixedBitSet::with_capacity(self.instance.vertices.len());
+        unvisited.insert_range(1..);
+
+        DarpState {
+            unvisited,
+            current: 0,
+            network: self.instance.create_route_network(),
+            n_vehicles: 1,
+        }
+    }
+
+    fn get_successors(
+        &self,
+        state: &Self::State,
+    ) -> impl IntoIterator<Item = (Self::State, Self::CostType, usize)> {
+        let n = self.instance.n_requests;
+        let load = self.get_load(state);
+        let mut successors = state
+            .unvisited
+            .ones()
+            .filter_map(|next| {
+                if next > n && state.unvisited.contains(next - n) {
+                    return None;
+                }
+
+                if load + self.instance.demands[next] > self.instance.capacity {
+                    return None;
+                }
+
+                let successor = self.visit(state, next, false)?;
+
+                Some((
+                    successor,
+                    self.instance.distances[state.current][next],
+                    next,
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        // A new route starts only after all requests on board are delivered.
+        if state.current != 0
+            && load == 0
+            && state.n_vehicles < self.n_vehicles
+            && self
+                .instance
+                .can_return_to_depot(&state.network, state.current, false)
+        {
+            let n_nodes = self.instance.vertices.len();
+
+            successors.extend(state.unvisited.ones().filter_map(|next| {
+                if next > n || self.instance.demands[next] > self.instance.capacity {
+                    return None;
+                }
+
+                let successor = self.visit(state, next, true)?;
+                let weight =
+                    self.instance.distances[state.current][0] + self.instance.distances[0][next];
+
+                Some((successor, weight, n_nodes + next))
+            }));
+        }
+
+        successors
+    }
+
+    fn get_base_cost(&self, state: &Self::State) -> Option<Self::CostType> {
+        if state.unvisited.is_clear()
+            && self
+                .instance
+                .can_return_to_depot(&state.network, state.current, false)
+        {
+            Some(self.instance.distances[state.current][0])
+        } else {
+            None
+        }
+    }
+}
+
+impl Dominance for Darp {
+    type State = DarpState;
+    type Key = (FixedBitSet, usize);
+
+    fn get_key(&self, state: &Self::State) -> Self::Key {
+        (state.unvisited.clone(), state.current)
+    }
+
+    // The requests on board are the same in states with the same key,
+    // and larger distances in the network allow more schedules.
+    fn compare(&self, a: &Self::State, b: &Self::State) -> Option<Ordering> {
+        let mut ordering = b.n_vehicles.cmp(&a.n_vehicles);
+
+        for o in a
+            .network
+            .from_current
+            .iter()
+            .zip(b.network.from_current.iter())
+            .chain(
+                a.network
+                    .from_origin
+                    .iter()
+                    .zip(b.network.from_origin.iter()),
+            )
+            .map(|(a, b)| a.cmp(b))
+        {
+            match (ordering, o) {
+                (_, Ordering::Equal) => {}
+                (Ordering::Equal, o) => ordering = o,
+                (ordering, o) if ordering != o => return None,
+                _ => {}
+            }
+        }
+
+        Some(ordering)
+    }
+}
+
+impl Bound for Darp {
+    type State = DarpState;
+    type CostType = i32;
+
+    fn get_dual_bound(&self, state: &Self::State) -> Option<Self::CostType> {
+        let bound_to = state.unvisited.ones().map(|i| self.min_to[i]).sum::<i32>() + self.min_to[0];
+        let bound_from = state
+            .unvisited
+            .ones()
+            .map(|i| self.min_from[i])
+            .sum::<i32>()
+            + self.min_from[state.current];
+
+        Some(cmp::max(bound_to, bound_from))
+    }
+}
+
+fn main() {
+    let timer = Timer::default();
+    let args = Args::parse();
+
+    let instance = Instance::read_from_file(&args.input_file).unwrap();
+    let mut instance = RoundedInstance::new(instance, args.round_to);
+
+    if let Some(vehicles) = args.vehicles {
+        instance.n_vehicles = vehicles;
+    }
+
+    println!("Number of vehicles: {}", instance.n_vehicles);
+
+    let darp = Darp::from(instance.clone());
+
+    let parameters = SearchParameters {
+        time_limit: Some(args.time_limit),
+        ..Default::default()
+    };
+    let solution = match args.solver {
+        SolverChoice::Cabs => {
+            let cabs_parameters = CabsParameters::default();
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let mut solver = solvers::create_cabs(darp, parameters, cabs_parameters);
+            io::run_solver_and_dump_solution_history(&mut solver, &args.history).unwrap()
+        }
+        SolverChoice::Astar => {
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let mut solver = solvers::create_astar(darp, parameters);
+            io::run_solver_and_dump_solution_history(&mut solver, &args.history).unwrap()
+        }
+    };
+    io::print_solution_statistics(&solution);
+
+    if let Some(cost) = solution.cost {
+        let routes = instance.transitions_to_routes(&solution.transitions);
+        instance.print_solution(&routes);
+
+        if instance.validate(&routes, cost) {
+            println!("The solution is valid.");
+        } else {
+            println!("The solution is invalid.");
+        }
+    }
+}
@@ -0,0 +1,607 @@
+use clap::{Parser, ValueEnum};
+use rpid::algorithms;
+use std::cmp;
+use std::error::Error;
+use std::fs;
+
+/// Dial-a-ride instance in the format of Cordeau (2006).
+///
+/// Node 0 is the depot, nodes 1 to n are pickups, and node n + i is the delivery of request i.
+#[derive(Clone, Debug)]
+pub struct Instance {
+    pub n_vehicles: usize,
+    pub max_route_duration: f64,
+    pub capacity: i32,
+    pub max_ride_time: f64,
+    pub vertices: Vec<usize>,
+    pub coordinates: Vec<(f64, f64)>,
+    pub service_times: Vec<f64>,
+    pub demands: Vec<i32>,
+    pub opening: Vec<f64>,
+    pub closing: Vec<f64>,
+}
+
+impl Instance {
+    /// Reads an instance from a file.
+    ///
+    /// The first line gives the number of vehicles, the number of requests or nodes,
+    /// the maximum route duration, the capacity, and the maximum ride time.
+    /// Each of the following lines gives the id, the coordinates, the service time, the demand,
+    /// and the time window of a node.
+    /// The number of requests is determined from the number of nodes since the second value
+    /// of the first line differs between instance sets, and a copy of the depot at the end is ignored.
+    pub fn read_from_file(filename: &str) -> Result<Self, Box<dyn Error>> {
+        let file = fs::read_to_string(filename)?;
+        let mut lines = file.lines().filter(|line| !line.trim().is_empty());
+
+        let line = lines.next().ok_or("failed to read the first line")?;
+        let mut digits = line.split_whitespace();
+        let n_vehicles = digits
+            .next()
+            .ok_or("failed to parse the number of vehicles")?
+            .parse()?;
+        digits.next();
+        let max_route_duration = digits
+            .next()
+            .ok_or("failed to parse the maximum route duration")?
+            .parse()?;
+        let capacity = digits
+            .next()
+            .ok_or("failed to parse the capacity")?
+            .parse()?;
+        let max_ride_time = digits
+            .next()
+            .ok_or("failed to parse the maximum ride time")?
+            .parse()?;
+
+        let mut vertices = vec![];
+        let mut coordinates = vec![];
+        let mut service_times = vec![];
+        let mut demands = vec![];
+        let mut opening = vec![];
+        let mut closing = vec![];
+
+        for (i, line) in lines.enumerate() {
+            let digits = line.split_whitespace().collect::<Vec<_>>();
+
+            if digits.len() < 7 {
+                return Err(format!("failed to parse the {}-th node", i).into());
+            }
+
+            vertices.push(digits[0].parse()?);
+            coordinates.push((digits[1].parse()?, digits[2].parse()?));
+            service_times.push(digits[3].parse()?);
+            demands.push(digits[4].parse::<f64>()?.round() as i32);
+            opening.push(digits[5].parse()?);
+            closing.push(digits[6].parse()?);
+        }
+
+        let n = (vertices.len() - 1) / 2;
+
+        if n == 0 {
+            return Err("no request".into());
+        }
+
+        vertices.truncate(2 * n + 1);
+        coordinates.truncate(2 * n + 1);
+        service_times.truncate(2 * n + 1);
+        demands.truncate(2 * n + 1);
+        opening.truncate(2 * n + 1);
+        closing.truncate(2 * n + 1);
+
+        if (1..=n).any(|i| demands[i] <= 0 || demands[n + i] != -demands[i]) {
+            return Err("the demand of a pickup must be delivered by its delivery".into());
+        }
+
+        Ok(Self {
+            n_vehicles,
+            max_route_duration,
+            capacity,
+            max_ride_time,
+            vertices,
+            coordinates,
+            service_times,
+            demands,
+            opening,
+            closing,
+        })
+    }
+}
+
+/// Dial-a-ride instance with times and distances multiplied by a power of ten and truncated.
+///
+/// The travel time of an edge is equal to its cost.
+/// The start of the service at a node can be delayed after the arrival,
+/// e.g., to shorten the ride time of a request picked up there.
+/// The ride time of a request is from the end of the service at the pickup
+/// to the start of the service at the delivery.
+#[derive(Clone, Debug)]
+pub struct RoundedInstance {
+    pub n_vehicles: usize,
+    pub n_requests: usize,
+    pub max_route_duration: i32,
+    pub capacity: i32,
+    pub max_ride_time: i32,
+    pub vertices: Vec<usize>,
+    pub distances: Vec<Vec<i32>>,
+    pub service_times: Vec<i32>,
+    pub demands: Vec<i32>,
+    pub opening: Vec<i32>,
+    pub closing: Vec<i32>,
+}
+
+impl RoundedInstance {
+    pub fn new(instance: Instance, round_to: u32) -> Self {
+        let pow = 10f64.powf(round_to as f64);
+        let round = |t: f64| (t * pow).trunc() as i32;
+
+        let distances = algorithms::compute_pairwise_euclidean_distances(&instance.coordinates);
+        let distances = distances
+            .into_iter()
+            .map(|row| row.into_iter().map(round).collect())
+            .collect();
+        let service_times = instance.service_times.into_iter().map(round).collect();
+        let opening = instance.opening.into_iter().map(round).collect();
+        let closing = instance.closing.into_iter().map(round).collect();
+
+        Self {
+            n_vehicles: instance.n_vehicles,
+            n_requests: (instance.vertices.len() - 1) / 2,
+            max_route_duration: round(instance.max_route_duration),
+            capacity: instance.capacity,
+            max_ride_time: round(instance.max_ride_time),
+            vertices: instance.vertices,
+            distances,
+            service_times,
+            demands: instance.demands,
+            opening,
+            closing,
+        }
+    }
+
+    /// Returns the time to serve `i` and then travel to `j`.
+    pub fn get_travel_time(&self, i: usize, j: usize) -> i32 {
+        self.service_times[i] + self.distances[i][j]
+    }
+
+    pub fn validate(&self, routes: &[Vec<usize>], cost: i32) -> bool {
+        if routes.len() > self.n_vehicles {
+            println!(
+                "Invalid number of vehicles {} > {}",
+                routes.len(),
+                self.n_vehicles
+            );
+
+            return false;
+        }
+
+        let n = self.vertices.len();
+        let mut visited_by = vec![None; n];
+
+        for (k, route) in routes.iter().enumerate() {
+            for &v in route {
+                if v == 0 || v >= n {
+                    println!("Invalid node {} visited by route {}", v, k);
+
+                    return false;
+                }
+
+                if let Some(l) = visited_by[v] {
+                    println!("Node {} visited twice by routes {} and {}", v, l, k);
+
+                    return false;
+                }
+
+                visited_by[v] = Some(k);
+            }
+        }
+
+        if let Some(v) = (1..n).find(|&v| visited_by[v].is_none()) {
+            println!("Node {} is not visited", v);
+
+            return false;
+        }
+
+        let mut recomputed_cost = 0;
+
+        for route in routes {
+            if let Some(c) = self.evaluate_route(route, true) {
+                recomputed_cost += c;
+            } else {
+                return false;
+            }
+        }
+
+        if recomputed_cost != cost {
+            println!("Invalid cost {} != {}", recomputed_cost, cost);
+
+            return false;
+        }
+
+        true
+    }
+
+    /// Returns the cost of a route starting and ending at the depot, or `None` if it is infeasible.
+    fn evaluate_route(&self, route: &[usize], verbose: bool) -> Option<i32> {
+        let n = self.n_requests;
+        let mut network = self.create_route_network();
+        let mut current = 0;
+        let mut load = 0;
+        let mut cost = 0;
+
+        for &v in route {
+            if v > n && !network.is_on_board(v - n) {
+                if verbose {
+                    println!("Node {} is visited before its pickup in the route", v);
+                }
+
+                return None;
+            }
+
+            network = self.extend_route_network(&network, current, v, verbose)?;
+            cost += self.distances[current][v];
+            load += self.demands[v];
+
+            if load > self.capacity {
+                if verbose {
+                    println!(
+                        "Vehicle load exceeded {} > {} at node {}",
+                        load, self.capacity, v
+                    );
+                }
+
+                return None;
+            }
+
+            current = v;
+        }
+
+        if let Some(i) = (1..=n).find(|&i| network.is_on_board(i)) {
+            if verbose {
+                println!("Request {} is not delivered in the route", i);
+            }
+
+            return None;
+        }
+
+        if !self.can_return_to_depot(&network, current, verbose) {
+            return None;
+        }
+
+        cost += self.distances[current][0];
+
+        Some(cost)
+    }
+
+    /// Returns the network of a route at the depot before leaving it.
+    pub fn create_route_network(&self) -> RouteNetwork {
+        let mut from_current = vec![0; self.n_requests + 2];
+        from_current[0] = -self.opening[0];
+        let mut from_origin = vec![0; self.n_requests + 2];
+        from_origin[1] = self.closing[0];
+
+        RouteNetwork {
+            from_current,
+            from_origin,
+            on_board: vec![false; self.n_requests + 1],
+        }
+    }
+
+    /// Returns the network after visiting `next` from `current`, or `None` if no schedule exists.
+    ///
+    /// `next` must be a pickup or the delivery of a request on board.
+    pub fn extend_route_network(
+        &self,
+        network: &RouteNetwork,
+        current: usize,
+        next: usize,
+        verbose: bool,
+    ) -> Option<RouteNetwork> {
+        let n = self.n_requests;
+        let travel_time = self.get_travel_time(current, next);
+
+        // The service at `next` starts after the arrival from `current` and after its opening.
+        let from_next = network
+            .from_current
+            .iter()
+            .zip(network.from_origin.iter())
+            .map(|(&c, &o)| cmp::min(c - travel_time, o - self.opening[next]))
+            .collect::<Vec<_>>();
+
+        let earliest = -from_next[0];
+
+        if earliest > self.closing[next] {
+            if verbose {
+                println!(
+                    "Time window violation: node {} is visited at {} after {}",
+                    next, earliest, self.closing[next]
+                );
+            }
+
+            return None;
+        }
+
+        let mut latest = self.closing[next];
+
+        if next > n {
+            let i = next - n;
+            let min_ride_time = -from_next[1 + i] - self.service_times[i];
+
+            if min_ride_time > self.max_ride_time {
+                if verbose {
+                    println!(
+                        "Ride time violation: request {} takes at least {} > {}",
+                        i, min_ride_time, self.max_ride_time
+                    );
+                }
+
+                return None;
+            }
+
+            latest = cmp::min(
+                latest,
+                network.from_origin[1 + i] + self.service_times[i] + self.max_ride_time,
+            );
+        }
+
+        let mut from_origin = network
+            .from_origin
+            .iter()
+            .zip(from_next.iter())
+            .map(|(&o, &d)| cmp::min(o, latest + d))
+            .collect::<Vec<_>>();
+        let mut from_current = from_next;
+        let mut on_board = network.on_board.clone();
+
+        if next <= n {
+            from_current[1 + next] = 0;
+            from_origin[1 + next] = latest;
+            on_board[next] = true;
+        } else {
+            from_current[1 + next - n] = 0;
+            from_origin[1 + next - n] = 0;
+            on_board[next - n] = false;
+        }
+
+        // The pickups not on board do not constrain the rest of the route.
+        for i in (1..=n).filter(|&i| !on_board[i]) {
+            from_current[1 + i] = 0;
+            from_origin[1 + i] = 0;
+        }
+
+        Some(RouteNetwork {
+            from_current,
+            from_origin,
+            on_board,
+        })
+    }
+
+    /// Checks if the vehicle can return to the depot from `current` in time.
+    pub fn can_return_to_depot(
+        &self,
+        network: &RouteNetwork,
+        current: usize,
+        verbose: bool,
+    ) -> bool {
+        let travel_time = self.get_travel_time(current, 0);
+        let arrival = travel_time - network.from_current[0];
+
+        if arrival > self.closing[0] {
+            if verbose {
+                println!(
+                    "Time window violation: the vehicle returns to the depot at {} after {}",
+                    arrival, self.closing[0]
+                );
+            }
+
+            return false;
+        }
+
+        let duration = travel_time - network.from_current[1];
+
+        if duration > self.max_route_duration {
+            if verbose {
+                println!(
+                    "Route duration exceeded {} > {}",
+                    duration, self.max_route_duration
+                );
+            }
+
+            return false;
+        }
+
+        true
+    }
+
+    /// Splits transitions of the models into routes.
+    ///
+    /// A transition `i` visits node `i` in the current route,
+    /// and a transition `i >= 2n + 1` visits pickup `i - 2n - 1` in a new route.
+    pub fn transitions_to_routes(&self, transitions: &[usize]) -> Vec<Vec<usize>> {
+        let n = self.vertices.len();
+        let mut routes = vec![vec![]];
+
+        for &transition in transitions {
+            if transition >= n {
+                routes.push(vec![transition - n]);
+            } else {
+                routes.last_mut().unwrap().push(transition);
+            }
+        }
+
+        routes
+    }
+
+    pub fn print_solution(&self, routes: &[Vec<usize>]) {
+        for (k, route) in routes.iter().enumerate() {
+            println!(
+                "Route {}: {}",
+                k + 1,
+                route
+                    .iter()
+                    .map(|&i| self.vertices[i].to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
+        }
+    }
+}
+
+/// Time constraints of a partial route as a simple temporal network.
+///
+/// A node of the network is the start time of a service, and an arc bounds the difference of two start times,
+/// so a schedule exists if and only if the network has no negative cycle.
+/// Only the shortest-path distances from the current node and from the time origin are kept
+/// to the time points that can constrain the rest of the route:
+/// the time origin (index 0), the departure from the depot (index 1), and the pickup of each request on board
+/// (index `1 + i`), whose entries are zero if not on board.
+/// Since a larger distance is a looser constraint, a network with larger distances dominates.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RouteNetwork {
+    pub from_current: Vec<i32>,
+    pub from_origin: Vec<i32>,
+    pub on_board: Vec<bool>,
+}
+
+impl RouteNetwork {
+    /// Returns the earliest start time of the service at the current node.
+    pub fn get_earliest_time(&self) -> i32 {
+        -self.from_current[0]
+    }
+
+    pub fn is_on_board(&self, i: usize) -> bool {
+        self.on_board[i]
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum SolverChoice {
+    Cabs,
+    Astar,
+}
+
+#[derive(Debug, Parser)]
+pub struct Args {
+    #[arg(help = "Input file")]
+    pub input_file: String,
+    #[arg(short, long, value_enum, default_value_t = SolverChoice::Cabs, help = "Solver")]
+    pub solver: SolverChoice,
+    #[arg(long, default_value_t = String::from("history.csv"), help = "File to save the history")]
+    pub history: String,
+    #[arg(short, long, default_value_t = 1800.0, help = "Time limit")]
+    pub time_limit: f64,
+    #[arg(
+        short,
+        long,
+        default_value_t = 2,
+        help = "Number of decimal places to round the distances and times"
+    )]
+    pub round_to: u32,
+    #[arg(long, help = "Number of vehicles (read from the file if omitted)")]
+    pub vehicles: Option<usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two requests on a line: the depot at 0, pickup 1 at 10, pickup 2 at 60,
+    // delivery 1 at 30, and delivery 2 at 70.
+    fn create_instance() -> RoundedInstance {
+        let positions: [i32; 5] = [0, 10, 60, 30, 70];
+        let distances = positions
+            .iter()
+            .map(|&x| positions.iter().map(|&y| (x - y).abs()).collect())
+            .collect();
+
+        RoundedInstance {
+            n_vehicles: 1,
+            n_requests: 2,
+            max_route_duration: 1000,
+            capacity: 2,
+            max_ride_time: 25,
+            vertices: vec![0, 1, 2, 3, 4],
+            distances,
+            service_times: vec![0; 5],
+            demands: vec![0, 1, 1, -1, -1],
+            opening: vec![0; 5],
+            closing: vec![1000; 5],
+        }
+    }
+
+    fn extend_route(instance: &RoundedInstance, route: &[usize]) -> Option<RouteNetwork> {
+        let mut network = instance.create_route_network();
+        let mut current = 0;
+
+        for &next in route {
+            network = instance.extend_route_network(&network, current, next, false)?;
+            current = next;
+        }
+
+        Some(network)
+    }
+
+    #[test]
+    fn extend_route_network_computes_earliest_time() {
+        let instance = create_instance();
+
+        let network = extend_route(&instance, &[1, 3, 2, 4]).unwrap();
+        assert_eq!(network.get_earliest_time(), 70);
+        assert!(!network.is_on_board(1));
+        assert!(!network.is_on_board(2));
+        assert!(instance.can_return_to_depot(&network, 4, false));
+        assert_eq!(instance.evaluate_route(&[1, 3, 2, 4], false), Some(140));
+    }
+
+    #[test]
+    fn extend_route_network_detects_ride_time_violation() {
+        let instance = create_instance();
+
+        // Request 1 rides from 10 to 30 via 60.
+        let network = extend_route(&instance, &[1, 2]).unwrap();
+        assert!(network.is_on_board(1));
+        assert!(network.is_on_board(2));
+        assert!(instance
+            .extend_route_network(&network, 2, 3, false)
+            .is_none());
+    }
+
+    #[test]
+    fn extend_route_network_detects_time_window_violation() {
+        let mut instance = create_instance();
+        instance.closing[2] = 50;
+
+        assert!(extend_route(&instance, &[1, 3]).is_some());
+        assert!(extend_route(&instance, &[1, 3, 2]).is_none());
+    }
+
+    #[test]
+    fn extend_route_network_delays_pickup_to_shorten_ride_time() {
+        let mut instance = create_instance();
+        instance.opening[3] = 100;
+
+        // The pickup of request 1 is delayed to at least 75, and the departure from the depot up to 70.
+        let network = extend_route(&instance, &[1, 3]).unwrap();
+        assert_eq!(network.get_earliest_time(), 100);
+
+        instance.max_route_duration = 60;
+        assert!(instance.can_return_to_depot(&network, 3, false));
+        instance.max_route_duration = 59;
+        assert!(!instance.can_return_to_depot(&network, 3, false));
+
+        // The pickup cannot be delayed enough.
+        instance.closing[1] = 74;
+        assert!(extend_route(&instance, &[1, 3]).is_none());
+    }
+
+    #[test]
+    fn can_return_to_depot_detects_time_window_violation() {
+        let mut instance = create_instance();
+        let network = extend_route(&instance, &[1, 3, 2, 4]).unwrap();
+
+        instance.closing[0] = 140;
+        assert!(instance.can_return_to_depot(&network, 4, false));
+        instance.closing[0] = 139;
+        assert!(!instance.can_return_to_depot(&network, 4, false));
+    }
+}
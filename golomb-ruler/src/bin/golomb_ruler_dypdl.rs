@@ -85,7 +85,7 @@ fn main() {
                 & Condition::comparison_e(
                     ComparisonOperator::Le,
                     i,
-                    (n * n + 1) / 2 - lower_bounds.element(n / 2 - n_marks),
+                    (n * n).div_ceil(2) - lower_bounds.element(n / 2 - n_marks),
                 ))
                 | Condition::comparison_e(ComparisonOperator::Ge, n_marks, n / 2)
                     & Condition::comparison_e(
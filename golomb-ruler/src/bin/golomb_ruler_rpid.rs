@@ -54,7 +54,7 @@ impl Dp for GolomobRuler {
         let lb = state.last_mark + 1;
 
         let ub = if state.n_marks < self.n / 2 {
-            (self.n * self.n + 1) / 2 - self.lower_bounds[self.n / 2 - state.n_marks] as usize
+            (self.n * self.n).div_ceil(2) - self.lower_bounds[self.n / 2 - state.n_marks] as usize
         } else {
             self.n * self.n + 1 - self.lower_bounds[self.n - state.n_marks] as usize
         };
@@ -22,7 +22,7 @@ pub fn validate(n: usize, marks: &[usize], length: usize) -> bool {
     let mut max_distance = 0;
 
     for (&i, &j) in marks.iter().tuple_combinations() {
-        let distance = if i > j { i - j } else { j - i };
+        let distance = i.abs_diff(j);
 
         if distance_set.contains(distance) {
             println!("Distance {} is repeated", distance);
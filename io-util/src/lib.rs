@@ -1,9 +1,17 @@
 use dypdl::variable_type::Numeric;
 use dypdl_heuristic_search::{Search, Solution};
+use fixedbitset::FixedBitSet;
+use rpid::solvers::search_algorithms::{self, Cabs, DualBoundNode, SearchNode};
+use rpid::{Bound, Dominance, Dp, OptimizationMode};
+use std::cell::Cell;
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fmt::Display;
-use std::fs::OpenOptions;
+use std::fs::{self, OpenOptions};
+use std::hash::Hash;
 use std::io::Write;
+use std::ops::Neg;
+use std::rc::Rc;
 
 /// Run a solver and dump the solution history to a CSV file.
 ///
@@ -81,3 +89,975 @@ where
     println!("Expanded: {}", solution.expanded);
     println!("Generated: {}", solution.generated);
 }
+
+/// Create an RPID CABS solver that also prunes nodes by a primal bound shared with the caller.
+///
+/// The shared primal bound can be updated during search, e.g., with the cost of a solution
+/// improved by local search, and is used in addition to the primal bound of the solver.
+pub fn create_cabs_with_shared_primal_bound<D, S, C, K>(
+    dp: D,
+    mut parameters: rpid::solvers::SearchParameters<C>,
+    cabs_parameters: rpid::solvers::CabsParameters,
+    shared_primal_bound: Rc<Cell<Option<C>>>,
+) -> impl rpid::solvers::Search<CostType = C>
+where
+    D: Dp<State = S, CostType = C> + Dominance<State = S, Key = K> + Bound<State = S, CostType = C>,
+    C: Ord + Copy + Neg<Output = C> + Display,
+    K: Hash + Eq,
+{
+    let solution_shared_primal_bound = shared_primal_bound.clone();
+    let select_primal_bound =
+        move |dp: &D, primal_bound: Option<C>| match (primal_bound, shared_primal_bound.get()) {
+            (Some(bound), Some(shared_bound)) if dp.is_better_cost(shared_bound, bound) => {
+                Some(shared_bound)
+            }
+            (None, shared_bound) => shared_bound,
+            (bound, _) => bound,
+        };
+    let root_select_primal_bound = select_primal_bound.clone();
+    let root_node_constructor = move |dp: &D, primal_bound| {
+        DualBoundNode::create_root(
+            dp,
+            dp.get_target(),
+            dp.get_identity_weight(),
+            root_select_primal_bound(dp, primal_bound),
+        )
+    };
+    let node_constructor =
+        move |dp: &_, state, cost, transition, parent: &DualBoundNode<_, _, _>, primal_bound| {
+            parent.create_child(
+                dp,
+                state,
+                cost,
+                transition,
+                select_primal_bound(dp, primal_bound),
+                None,
+            )
+        };
+    let solution_checker = move |dp: &D, node: &DualBoundNode<_, _, _>| {
+        node.check_solution(dp).filter(|&(cost, _)| {
+            solution_shared_primal_bound
+                .get()
+                .is_none_or(|shared_bound| dp.is_better_cost(cost, shared_bound))
+        })
+    };
+    let beam_search_closure = move |dp: &_, root_node, parameters: &_| {
+        search_algorithms::beam_search(
+            dp,
+            root_node,
+            node_constructor.clone(),
+            solution_checker.clone(),
+            parameters,
+        )
+    };
+    parameters.update_bounds(&dp);
+
+    Cabs::new(
+        dp,
+        root_node_constructor,
+        beam_search_closure,
+        parameters,
+        cabs_parameters,
+    )
+}
+
+/// Run an RPID solver, improve each new solution by local search, and dump the solution history to a CSV file.
+///
+/// `improve` takes the transitions of a solution and returns the cost and transitions of an improved solution if found.
+/// The best cost is stored in `shared_primal_bound`, which should be shared with the solver to prune nodes.
+/// The fields are the same as `rpid::io::run_solver_and_dump_solution_history`,
+/// followed by the cost after local search.
+pub fn run_solver_with_local_search_and_dump_solution_history<S, C>(
+    solver: &mut S,
+    filename: &str,
+    optimization_mode: OptimizationMode,
+    shared_primal_bound: &Cell<Option<C>>,
+    mut improve: impl FnMut(&[usize]) -> Option<(C, Vec<usize>)>,
+) -> Result<rpid::Solution<C>, Box<dyn Error>>
+where
+    S: rpid::solvers::Search<CostType = C>,
+    C: PartialOrd + Display + Copy,
+{
+    let is_better_cost = |new_cost: C, old_cost: C| match optimization_mode {
+        OptimizationMode::Minimization => new_cost < old_cost,
+        OptimizationMode::Maximization => new_cost > old_cost,
+    };
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(filename)?;
+    let mut best_solution: Option<(C, Vec<usize>)> = None;
+
+    loop {
+        let (mut solution, terminated) = solver.search_next();
+
+        if let Some(cost) = solution.cost {
+            if best_solution
+                .as_ref()
+                .is_none_or(|(best_cost, _)| is_better_cost(cost, *best_cost))
+            {
+                let improved = improve(&solution.transitions)
+                    .filter(|(improved_cost, _)| is_better_cost(*improved_cost, cost))
+                    .unwrap_or_else(|| (cost, solution.transitions.clone()));
+                shared_primal_bound.set(Some(improved.0));
+                best_solution = Some(improved);
+            }
+
+            let transitions = solution
+                .transitions
+                .iter()
+                .map(|t| format!("{}", t))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let improved_cost = best_solution.as_ref().unwrap().0;
+
+            let line = if let Some(bound) = solution.best_bound {
+                format!(
+                    "{}, {}, {}, {}, {}, {}, {}\n",
+                    solution.time,
+                    cost,
+                    bound,
+                    transitions,
+                    solution.expanded,
+                    solution.generated,
+                    improved_cost
+                )
+            } else {
+                format!(
+                    "{}, {}, , {}, {}, {}, {}\n",
+                    solution.time,
+                    cost,
+                    transitions,
+                    solution.expanded,
+                    solution.generated,
+                    improved_cost
+                )
+            };
+            file.write_all(line.as_bytes())?;
+            file.flush()?;
+        }
+
+        if terminated {
+            if let Some((best_cost, transitions)) = best_solution {
+                if solution
+                    .cost
+                    .is_none_or(|cost| is_better_cost(best_cost, cost))
+                {
+                    solution.cost = Some(best_cost);
+                    solution.transitions = transitions;
+                }
+
+                // Nodes pruned by the shared primal bound are not reflected in the bound of the solver.
+                if solution.is_optimal
+                    || solution
+                        .best_bound
+                        .is_some_and(|bound| !is_better_cost(bound, best_cost))
+                {
+                    solution.best_bound = Some(best_cost);
+                    solution.is_optimal = true;
+                }
+            }
+
+            return Ok(solution);
+        }
+    }
+}
+
+/// Improve a sequence by 2-opt and or-opt moves until no improving move is found.
+///
+/// `evaluate` returns the cost of a sequence to be minimized, or `None` if the sequence is infeasible.
+/// Returns the improved cost and sequence if an improvement is found.
+pub fn improve_sequence_by_local_search<C>(
+    sequence: &[usize],
+    evaluate: impl Fn(&[usize]) -> Option<C>,
+) -> Option<(C, Vec<usize>)>
+where
+    C: PartialOrd + Copy,
+{
+    let n = sequence.len();
+    let mut best_cost = evaluate(sequence)?;
+    let mut best_sequence = sequence.to_vec();
+    let mut is_improved = false;
+
+    'search: loop {
+        for i in 0..n {
+            for j in i + 1..n {
+                let mut candidate = best_sequence.clone();
+                candidate[i..=j].reverse();
+
+                if let Some(cost) = evaluate(&candidate).filter(|&cost| cost < best_cost) {
+                    best_cost = cost;
+                    best_sequence = candidate;
+                    is_improved = true;
+
+                    continue 'search;
+                }
+            }
+        }
+
+        for length in 1..=3.min(n) {
+            for i in 0..=n - length {
+                let mut remaining = best_sequence.clone();
+                let segment = remaining.drain(i..i + length).collect::<Vec<_>>();
+
+                for j in (0..=remaining.len()).filter(|&j| j != i) {
+                    let mut candidate = remaining.clone();
+                    candidate.splice(j..j, segment.iter().copied());
+
+                    if let Some(cost) = evaluate(&candidate).filter(|&cost| cost < best_cost) {
+                        best_cost = cost;
+                        best_sequence = candidate;
+                        is_improved = true;
+
+                        continue 'search;
+                    }
+                }
+            }
+        }
+
+        break;
+    }
+
+    if is_improved {
+        Some((best_cost, best_sequence))
+    } else {
+        None
+    }
+}
+
+/// Result of solving an instance, reported for each instance of a file.
+#[derive(Clone, Debug)]
+pub struct InstanceResult<C> {
+    pub cost: Option<C>,
+    pub bound: Option<C>,
+    pub is_optimal: bool,
+    pub is_valid: bool,
+    pub time: f64,
+}
+
+impl<C> InstanceResult<C>
+where
+    C: Display,
+{
+    /// Summarize the result in one line, where `objective` names the cost, e.g., `cost` or `profit`.
+    pub fn summarize(&self, objective: &str) -> String {
+        let status = match (&self.cost, self.is_valid) {
+            (None, _) => String::from("no solution"),
+            (Some(cost), true) => format!("{} {}", objective, cost),
+            (Some(cost), false) => format!("{} {} (invalid)", objective, cost),
+        };
+        let bound = self
+            .bound
+            .as_ref()
+            .map_or_else(|| String::from("-"), |bound| bound.to_string());
+        let optimal = if self.is_optimal { ", optimal" } else { "" };
+
+        format!(
+            "{}, bound {}{}, time {}s",
+            status, bound, optimal, self.time
+        )
+    }
+}
+
+/// Solve instances of a file one by one.
+///
+/// `instances` are pairs of the index of an instance in the file and the instance.
+/// `solve` takes an instance and its index, which is `None` if it is the only instance,
+/// and returns `None` if the instance is not supported.
+/// When multiple instances are solved, the results are summarized by `summarize` at the end.
+pub fn solve_instances<T, R>(
+    instances: Vec<(usize, T)>,
+    solve: impl Fn(T, Option<usize>) -> Option<R>,
+    summarize: impl Fn(&R) -> String,
+) {
+    if instances.len() == 1 {
+        let (_, instance) = instances.into_iter().next().unwrap();
+        solve(instance, None);
+
+        return;
+    }
+
+    let mut results = vec![];
+
+    for (index, instance) in instances {
+        println!("Instance {}", index);
+
+        if let Some(result) = solve(instance, Some(index)) {
+            results.push((index, result));
+        }
+    }
+
+    for (index, result) in results {
+        println!("Instance {}: {}", index, summarize(&result));
+    }
+}
+
+/// Insert an index before the extension of a file name, e.g., `history.csv` to `history_1.csv`.
+///
+/// The file name is returned as is if the index is `None`.
+pub fn add_index_to_filename(filename: &str, index: Option<usize>) -> String {
+    let Some(index) = index else {
+        return String::from(filename);
+    };
+
+    match filename.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() && !extension.contains('/') => {
+            format!("{}_{}.{}", stem, index, extension)
+        }
+        _ => format!("{}_{}", filename, index),
+    }
+}
+
+/// Optimal solution of an assignment problem, used to warm-start the Hungarian algorithm.
+///
+/// Potentials and the matching are indexed by the original indices of rows and columns.
+#[derive(Clone, Debug)]
+pub struct AssignmentSolution {
+    pub cost: i32,
+    pub row_potentials: Vec<i32>,
+    pub column_potentials: Vec<i32>,
+    pub row_to_column: Vec<Option<usize>>,
+}
+
+impl AssignmentSolution {
+    pub fn new(n: usize) -> Self {
+        Self {
+            cost: 0,
+            row_potentials: vec![0; n],
+            column_potentials: vec![0; n],
+            row_to_column: vec![None; n],
+        }
+    }
+}
+
+/// Solves the assignment problem between `rows` and `columns` with the Hungarian algorithm.
+///
+/// `weight(i, j)` returns the weight of assigning row `i` to column `j`, or `None` if forbidden.
+/// Pairs of the warm start that are still tight are kept, so only the remaining rows are augmented.
+/// The warm start must be optimal for a problem whose weights are not larger than the current ones.
+/// Returns `None` if there is no feasible assignment.
+pub fn solve_assignment_problem(
+    rows: &[usize],
+    columns: &[usize],
+    weight: impl Fn(usize, usize) -> Option<i32>,
+    warm_start: &AssignmentSolution,
+) -> Option<AssignmentSolution> {
+    const INFINITY: i64 = i64::MAX;
+
+    let k = rows.len();
+
+    if columns.len() != k {
+        return None;
+    }
+
+    let mut column_index = vec![None; warm_start.column_potentials.len()];
+
+    for (j, &column) in columns.iter().enumerate() {
+        column_index[column] = Some(j);
+    }
+
+    let mut u = rows
+        .iter()
+        .map(|&i| i64::from(warm_start.row_potentials[i]))
+        .collect::<Vec<_>>();
+    let mut v = columns
+        .iter()
+        .map(|&j| i64::from(warm_start.column_potentials[j]))
+        .chain(std::iter::once(0))
+        .collect::<Vec<_>>();
+    // The last column is a dummy column used as the root of the alternating tree.
+    let mut column_to_row = vec![None; k + 1];
+
+    for (i, &row) in rows.iter().enumerate() {
+        if let Some(j) = warm_start.row_to_column[row].and_then(|column| column_index[column]) {
+            if let Some(w) = weight(row, columns[j]) {
+                if i64::from(w) == u[i] + v[j] {
+                    column_to_row[j] = Some(i);
+                }
+            }
+        }
+    }
+
+    let mut is_matched = vec![false; k];
+    column_to_row
+        .iter()
+        .flatten()
+        .for_each(|&i| is_matched[i] = true);
+
+    for root in (0..k).filter(|&i| !is_matched[i]) {
+        column_to_row[k] = Some(root);
+        let mut min_reduced_cost = vec![INFINITY; k + 1];
+        let mut used = vec![false; k + 1];
+        let mut way = vec![k; k + 1];
+        let mut j0 = k;
+
+        while let Some(i0) = column_to_row[j0] {
+            used[j0] = true;
+            let mut delta = INFINITY;
+            let mut j1 = k;
+
+            for j in (0..k).filter(|&j| !used[j]) {
+                if let Some(w) = weight(rows[i0], columns[j]) {
+                    let reduced_cost = i64::from(w) - u[i0] - v[j];
+
+                    if reduced_cost < min_reduced_cost[j] {
+                        min_reduced_cost[j] = reduced_cost;
+                        way[j] = j0;
+                    }
+                }
+
+                if min_reduced_cost[j] < delta {
+                    delta = min_reduced_cost[j];
+                    j1 = j;
+                }
+            }
+
+            if delta == INFINITY {
+                return None;
+            }
+
+            for j in 0..=k {
+                if used[j] {
+                    u[column_to_row[j].unwrap()] += delta;
+                    v[j] -= delta;
+                } else if min_reduced_cost[j] != INFINITY {
+                    min_reduced_cost[j] -= delta;
+                }
+            }
+
+            j0 = j1;
+
+            if column_to_row[j0].is_none() {
+                break;
+            }
+        }
+
+        while j0 != k {
+            let j1 = way[j0];
+            column_to_row[j0] = column_to_row[j1];
+            j0 = j1;
+        }
+    }
+
+    let mut solution = warm_start.clone();
+    solution.cost = 0;
+
+    for (i, &row) in rows.iter().enumerate() {
+        solution.row_potentials[row] = u[i] as i32;
+        solution.row_to_column[row] = None;
+    }
+
+    for (j, &column) in columns.iter().enumerate() {
+        solution.column_potentials[column] = v[j] as i32;
+        let i = column_to_row[j].unwrap();
+        solution.row_to_column[rows[i]] = Some(column);
+        solution.cost += weight(rows[i], column).unwrap();
+    }
+
+    Some(solution)
+}
+
+/// Precedence graph inferred by preprocessing, dumped to inspect its results.
+///
+/// An arc `(i, j, rule)` means that node `i` must precede node `j` as inferred by `rule`,
+/// and a removed edge `(i, j, reason)` means that `j` cannot directly follow `i` due to `reason`.
+#[derive(Clone, Debug, Default)]
+pub struct PrecedenceGraph {
+    pub labels: Vec<String>,
+    pub arcs: Vec<(usize, usize, &'static str)>,
+    pub removed_edges: Vec<(usize, usize, &'static str)>,
+}
+
+impl PrecedenceGraph {
+    /// Returns the set of nodes reachable from each node by one or more arcs.
+    pub fn compute_transitive_closure(&self) -> Vec<FixedBitSet> {
+        let n = self.labels.len();
+        let mut successors = vec![FixedBitSet::with_capacity(n); n];
+
+        for &(i, j, _) in &self.arcs {
+            successors[i].insert(j);
+        }
+
+        (0..n)
+            .map(|i| {
+                let mut reachable = FixedBitSet::with_capacity(n);
+                let mut open = successors[i].ones().collect::<Vec<_>>();
+
+                while let Some(j) = open.pop() {
+                    if !reachable.put(j) {
+                        open.extend(successors[j].ones());
+                    }
+                }
+
+                reachable
+            })
+            .collect()
+    }
+
+    /// Returns the arcs of the transitive closure that are not implied by other arcs.
+    pub fn compute_transitive_reduction(&self, closure: &[FixedBitSet]) -> Vec<(usize, usize)> {
+        let n = self.labels.len();
+        let mut predecessors = vec![FixedBitSet::with_capacity(n); n];
+
+        for (i, successors) in closure.iter().enumerate() {
+            for j in successors.ones() {
+                predecessors[j].insert(i);
+            }
+        }
+
+        closure
+            .iter()
+            .enumerate()
+            .flat_map(|(i, successors)| {
+                let predecessors = &predecessors;
+
+                successors.ones().filter_map(move |j| {
+                    let is_implied = successors
+                        .intersection(&predecessors[j])
+                        .any(|k| k != i && k != j);
+
+                    if i != j && !is_implied {
+                        Some((i, j))
+                    } else {
+                        None
+                    }
+                })
+            })
+            .collect()
+    }
+
+    fn count_by_rule(edges: &[(usize, usize, &'static str)]) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+
+        for &(_, _, rule) in edges {
+            *counts.entry(rule).or_insert(0) += 1;
+        }
+
+        counts
+    }
+
+    /// Print the numbers of inferred arcs and removed edges.
+    pub fn print_summary(&self) {
+        let closure = self.compute_transitive_closure();
+        let reduction = self.compute_transitive_reduction(&closure);
+
+        println!("Inferred arcs: {}", self.arcs.len());
+
+        for (rule, count) in Self::count_by_rule(&self.arcs) {
+            println!("Inferred arcs by {}: {}", rule, count);
+        }
+
+        println!(
+            "Arcs in the transitive closure: {}",
+            closure.iter().map(|s| s.count_ones(..)).sum::<usize>()
+        );
+        println!("Arcs in the transitive reduction: {}", reduction.len());
+
+        if closure.iter().enumerate().any(|(i, s)| s.contains(i)) {
+            println!("The precedence graph has a cycle");
+        }
+
+        println!("Removed edges: {}", self.removed_edges.len());
+
+        for (reason, count) in Self::count_by_rule(&self.removed_edges) {
+            println!("Removed edges by {}: {}", reason, count);
+        }
+    }
+
+    /// Returns the graph in the Graphviz DOT format.
+    ///
+    /// Only the arcs in the transitive reduction are drawn,
+    /// and removed edges are drawn as dashed edges not affecting the layout.
+    pub fn to_dot(&self) -> String {
+        let closure = self.compute_transitive_closure();
+        let reduction = self.compute_transitive_reduction(&closure);
+        let mut rules = BTreeMap::new();
+
+        for &(i, j, rule) in &self.arcs {
+            rules.entry((i, j)).or_insert(rule);
+        }
+
+        let mut dot = String::from("digraph precedence {\n");
+
+        for (i, label) in self.labels.iter().enumerate() {
+            dot += &format!("    {} [label={}];\n", i, escape_string(label));
+        }
+
+        for (i, j) in reduction {
+            let rule = rules.get(&(i, j)).copied().unwrap_or("transitivity");
+            dot += &format!("    {} -> {} [label={}];\n", i, j, escape_string(rule));
+        }
+
+        for &(i, j, reason) in &self.removed_edges {
+            dot += &format!(
+                "    {} -> {} [label={}, style=dashed, color=red, constraint=false];\n",
+                i,
+                j,
+                escape_string(reason)
+            );
+        }
+
+        dot += "}\n";
+
+        dot
+    }
+
+    /// Returns the graph in the JSON format.
+    ///
+    /// Nodes are referred to by their indices in `nodes`.
+    pub fn to_json(&self) -> String {
+        let closure = self.compute_transitive_closure();
+        let reduction = self.compute_transitive_reduction(&closure);
+        let format_edges = |edges: &[(usize, usize, &'static str)], key: &str| {
+            edges
+                .iter()
+                .map(|&(i, j, rule)| {
+                    format!(
+                        "{{\"from\": {}, \"to\": {}, \"{}\": {}}}",
+                        i,
+                        j,
+                        key,
+                        escape_string(rule)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let format_counts = |counts: BTreeMap<&'static str, usize>| {
+            counts
+                .into_iter()
+                .map(|(rule, count)| format!("{}: {}", escape_string(rule), count))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let nodes = self
+            .labels
+            .iter()
+            .map(|label| escape_string(label))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let reduction_arcs = reduction
+            .iter()
+            .map(|(i, j)| format!("[{}, {}]", i, j))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let is_cyclic = closure.iter().enumerate().any(|(i, s)| s.contains(i));
+
+        let mut json = String::from("{\n");
+        json += &format!("  \"nodes\": [{}],\n", nodes);
+        json += &format!("  \"arcs\": [{}],\n", format_edges(&self.arcs, "rule"));
+        json += &format!("  \"transitive_reduction\": [{}],\n", reduction_arcs);
+        json += &format!(
+            "  \"removed_edges\": [{}],\n",
+            format_edges(&self.removed_edges, "reason")
+        );
+        json += "  \"summary\": {\n";
+        json += &format!("    \"arcs\": {},\n", self.arcs.len());
+        json += &format!(
+            "    \"arcs_by_rule\": {{{}}},\n",
+            format_counts(Self::count_by_rule(&self.arcs))
+        );
+        json += &format!(
+            "    \"transitive_closure_arcs\": {},\n",
+            closure.iter().map(|s| s.count_ones(..)).sum::<usize>()
+        );
+        json += &format!("    \"transitive_reduction_arcs\": {},\n", reduction.len());
+        json += &format!("    \"cyclic\": {},\n", is_cyclic);
+        json += &format!("    \"removed_edges\": {},\n", self.removed_edges.len());
+        json += &format!(
+            "    \"removed_edges_by_reason\": {{{}}}\n",
+            format_counts(Self::count_by_rule(&self.removed_edges))
+        );
+        json += "  }\n}\n";
+
+        json
+    }
+
+    /// Write the graph to `<prefix>.dot` in the Graphviz DOT format and to `<prefix>.json` in the JSON format.
+    ///
+    /// The extension `.dot` or `.json` of `filename` is removed to get the prefix if present.
+    pub fn dump(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+        let prefix = filename
+            .strip_suffix(".dot")
+            .or_else(|| filename.strip_suffix(".json"))
+            .unwrap_or(filename);
+        fs::write(format!("{}.dot", prefix), self.to_dot())?;
+        fs::write(format!("{}.json", prefix), self.to_json())?;
+
+        Ok(())
+    }
+}
+
+// Quotes a string so that it can be used in both DOT and JSON.
+fn escape_string(s: &str) -> String {
+    let mut escaped = String::from("\"");
+
+    for c in s.chars() {
+        match c {
+            '"' => escaped += "\\\"",
+            '\\' => escaped += "\\\\",
+            '\n' => escaped += "\\n",
+            c if c.is_control() => escaped += &format!("\\u{:04x}", c as u32),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Returns a random number generator with a linear congruential generator.
+    fn create_rng(seed: u64) -> impl FnMut(i32, i32) -> i32 {
+        let mut state = seed;
+
+        move |lb: i32, ub: i32| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+
+            lb + ((state >> 33) % (ub - lb + 1) as u64) as i32
+        }
+    }
+
+    // Returns all permutations of `items` by Heap's algorithm.
+    fn generate_permutations(items: &[usize]) -> Vec<Vec<usize>> {
+        fn generate(k: usize, items: &mut Vec<usize>, result: &mut Vec<Vec<usize>>) {
+            if k <= 1 {
+                result.push(items.clone());
+
+                return;
+            }
+
+            for i in 0..k {
+                generate(k - 1, items, result);
+                let j = if k.is_multiple_of(2) { i } else { 0 };
+                items.swap(j, k - 1);
+            }
+        }
+
+        let mut result = vec![];
+        generate(items.len(), &mut items.to_vec(), &mut result);
+
+        result
+    }
+
+    // Generates a weight matrix where about one in `forbidden` pairs is forbidden.
+    fn generate_weights(seed: u64, n: usize, forbidden: i32) -> Vec<Vec<Option<i32>>> {
+        let mut next = create_rng(seed);
+
+        (0..n)
+            .map(|_| {
+                (0..n)
+                    .map(|_| {
+                        let w = next(0, 50);
+
+                        if next(1, forbidden) == 1 {
+                            None
+                        } else {
+                            Some(w)
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn solve_assignment_problem_by_brute_force(
+        rows: &[usize],
+        columns: &[usize],
+        weights: &[Vec<Option<i32>>],
+    ) -> Option<i32> {
+        generate_permutations(columns)
+            .into_iter()
+            .filter_map(|permutation| {
+                rows.iter()
+                    .zip(permutation.iter())
+                    .map(|(&i, &j)| weights[i][j])
+                    .sum::<Option<i32>>()
+            })
+            .min()
+    }
+
+    // Checks that the solution matches `rows` to `columns` with the given cost.
+    fn check_assignment(
+        solution: &AssignmentSolution,
+        rows: &[usize],
+        columns: &[usize],
+        weights: &[Vec<Option<i32>>],
+    ) {
+        let mut assigned = rows
+            .iter()
+            .map(|&i| solution.row_to_column[i].unwrap())
+            .collect::<Vec<_>>();
+        let cost = rows
+            .iter()
+            .zip(assigned.iter())
+            .map(|(&i, &j)| weights[i][j].unwrap())
+            .sum::<i32>();
+        assert_eq!(cost, solution.cost);
+
+        assigned.sort();
+        let mut columns = columns.to_vec();
+        columns.sort();
+        assert_eq!(assigned, columns);
+    }
+
+    #[test]
+    fn solve_assignment_problem_matches_brute_force() {
+        for seed in 0..50 {
+            let n = 1 + seed as usize % 6;
+            let weights = generate_weights(seed, n, 4);
+            let all = (0..n).collect::<Vec<_>>();
+
+            let solution = solve_assignment_problem(
+                &all,
+                &all,
+                |i, j| weights[i][j],
+                &AssignmentSolution::new(n),
+            );
+            let expected = solve_assignment_problem_by_brute_force(&all, &all, &weights);
+
+            assert_eq!(solution.as_ref().map(|s| s.cost), expected);
+
+            if let Some(solution) = solution {
+                check_assignment(&solution, &all, &all, &weights);
+            }
+        }
+    }
+
+    #[test]
+    fn solve_assignment_problem_warm_start_matches_cold_start() {
+        for seed in 0..50 {
+            let n = 7;
+            let mut next = create_rng(seed + 1000);
+            let mut weights = generate_weights(seed, n, 10);
+            let mut rows = (0..n).collect::<Vec<_>>();
+            let mut columns = (0..n).collect::<Vec<_>>();
+            let mut warm_start = AssignmentSolution::new(n);
+
+            // Rows and columns are removed and weights are increased as in a search.
+            while !rows.is_empty() {
+                let warm =
+                    solve_assignment_problem(&rows, &columns, |i, j| weights[i][j], &warm_start);
+                let cold = solve_assignment_problem(
+                    &rows,
+                    &columns,
+                    |i, j| weights[i][j],
+                    &AssignmentSolution::new(n),
+                );
+                let expected = solve_assignment_problem_by_brute_force(&rows, &columns, &weights);
+
+                assert_eq!(warm.as_ref().map(|s| s.cost), expected);
+                assert_eq!(cold.as_ref().map(|s| s.cost), expected);
+
+                let Some(warm) = warm else {
+                    break;
+                };
+                check_assignment(&warm, &rows, &columns, &weights);
+
+                rows.remove(next(0, rows.len() as i32 - 1) as usize);
+                columns.remove(next(0, columns.len() as i32 - 1) as usize);
+                let i = next(0, n as i32 - 1) as usize;
+                let j = next(0, n as i32 - 1) as usize;
+                weights[i][j] = weights[i][j].map(|w| w + next(0, 20));
+                warm_start = warm;
+            }
+        }
+    }
+
+    #[test]
+    fn solve_assignment_problem_detects_infeasibility() {
+        let weights = [[Some(1), None], [Some(2), None]];
+        let all = [0, 1];
+
+        assert!(solve_assignment_problem(
+            &all,
+            &all,
+            |i, j| weights[i][j],
+            &AssignmentSolution::new(2)
+        )
+        .is_none());
+        assert!(solve_assignment_problem(
+            &all,
+            &all[..1],
+            |i, j| weights[i][j],
+            &AssignmentSolution::new(2)
+        )
+        .is_none());
+    }
+
+    // Returns the length of an open path visiting `sequence` in order,
+    // or `None` if a node is visited before its predecessor.
+    fn evaluate_path(
+        sequence: &[usize],
+        distances: &[Vec<i32>],
+        predecessors: &[Option<usize>],
+    ) -> Option<i32> {
+        let mut position = vec![0; sequence.len()];
+
+        for (k, &i) in sequence.iter().enumerate() {
+            position[i] = k;
+        }
+
+        if sequence
+            .iter()
+            .any(|&i| predecessors[i].is_some_and(|p| position[p] > position[i]))
+        {
+            return None;
+        }
+
+        Some(sequence.windows(2).map(|w| distances[w[0]][w[1]]).sum())
+    }
+
+    #[test]
+    fn improve_sequence_by_local_search_never_returns_worse_sequence() {
+        for seed in 0..100 {
+            let n = 2 + seed as usize % 9;
+            let mut next = create_rng(seed);
+            let distances = (0..n)
+                .map(|_| (0..n).map(|_| next(1, 100)).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            // Each node may have to be visited after a smaller node.
+            let predecessors = (0..n)
+                .map(|i| {
+                    if i > 0 && next(0, 2) == 0 {
+                        Some(next(0, i as i32 - 1) as usize)
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+            let evaluate = |sequence: &[usize]| evaluate_path(sequence, &distances, &predecessors);
+            let sequence = (0..n).collect::<Vec<_>>();
+            let initial_cost = evaluate(&sequence).unwrap();
+
+            let Some((cost, improved)) = improve_sequence_by_local_search(&sequence, evaluate)
+            else {
+                continue;
+            };
+
+            assert!(cost < initial_cost);
+            assert_eq!(evaluate(&improved), Some(cost));
+
+            let mut sorted = improved.clone();
+            sorted.sort();
+            assert_eq!(sorted, sequence);
+
+            // The improved sequence is a local optimum.
+            assert!(improve_sequence_by_local_search(&improved, evaluate).is_none());
+        }
+    }
+
+    #[test]
+    fn improve_sequence_by_local_search_rejects_infeasible_sequence() {
+        assert!(improve_sequence_by_local_search(&[0, 1, 2], |_| None::<i32>).is_none());
+    }
+}
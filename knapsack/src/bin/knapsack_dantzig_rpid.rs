@@ -13,17 +13,19 @@ static GLOBAL: Jemalloc = Jemalloc;
 
 struct Knapsack {
     instance: Instance,
+    fixed_profit: i32,
     sorted_items: Vec<(usize, i32, i32)>,
     epsilon: f64,
 }
 
 impl Knapsack {
-    fn new(instance: Instance, epsilon: f64) -> Self {
+    fn new(instance: Instance, fixed_profit: i32, epsilon: f64) -> Self {
         let sorted_items =
             algorithms::sort_knapsack_items_by_efficiency(&instance.weights, &instance.profits);
 
         Self {
             instance,
+            fixed_profit,
             sorted_items,
             epsilon,
         }
@@ -73,7 +75,7 @@ impl Dp for Knapsack {
 
     fn get_base_cost(&self, state: &Self::State) -> Option<Self::CostType> {
         if state.current == self.instance.profits.len() {
-            Some(0)
+            Some(self.fixed_profit)
         } else {
             None
         }
@@ -103,7 +105,7 @@ impl Bound for Knapsack {
 
     fn get_dual_bound(&self, state: &Self::State) -> Option<Self::CostType> {
         if state.current == self.instance.profits.len() {
-            return Some(0);
+            return Some(self.fixed_profit);
         }
 
         let sorted_weight_value_pairs = self.sorted_items.iter().filter_map(|&(i, w, p)| {
@@ -120,7 +122,7 @@ impl Bound for Knapsack {
             self.epsilon,
         );
 
-        Some(bound as i32)
+        Some(bound as i32 + self.fixed_profit)
     }
 }
 
@@ -129,7 +131,23 @@ fn main() {
     let args = Args::parse();
 
     let instance = Instance::read_from_file(&args.input_file).unwrap();
-    let knapsack = Knapsack::new(instance.clone(), args.epsilon);
+    let reduction = if args.reduce {
+        let reduction = instance.reduce();
+        reduction.print_report();
+
+        Some(reduction)
+    } else {
+        None
+    };
+    let knapsack = if let Some(reduction) = &reduction {
+        Knapsack::new(
+            reduction.instance.clone(),
+            reduction.fixed_profit,
+            args.epsilon,
+        )
+    } else {
+        Knapsack::new(instance.clone(), 0, args.epsilon)
+    };
 
     let parameters = SearchParameters {
         time_limit: Some(args.time_limit),
@@ -158,6 +176,11 @@ fn main() {
             .enumerate()
             .filter_map(|(i, &x)| if x == 0 { Some(i) } else { None })
             .collect::<Vec<_>>();
+        let packed_items = if let Some(reduction) = &reduction {
+            reduction.restore_solution(&packed_items)
+        } else {
+            packed_items
+        };
         instance.print_solution(&packed_items);
 
         if instance.validate(&packed_items, profit) {
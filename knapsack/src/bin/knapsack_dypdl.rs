@@ -21,23 +21,34 @@ fn main() {
 
     let instance = Instance::read_from_file(&args.input_file).unwrap();
 
+    let reduction = if args.reduce {
+        let reduction = instance.reduce();
+        reduction.print_report();
+
+        Some(reduction)
+    } else {
+        None
+    };
+    // The model is defined over the remaining items, and the profit of the fixed items is added at the end.
+    let (core, fixed_profit) = if let Some(reduction) = &reduction {
+        (&reduction.instance, reduction.fixed_profit)
+    } else {
+        (&instance, 0)
+    };
+
     let mut model = Model::default();
     model.set_maximize();
 
-    let n = instance.profits.len();
+    let n = core.profits.len();
     let item = model.add_object_type("item", n).unwrap();
 
     let current = model.add_element_variable("current", item, 0).unwrap();
     let remaining = model
-        .add_integer_resource_variable("remaining", false, instance.capacity)
+        .add_integer_resource_variable("remaining", false, core.capacity)
         .unwrap();
 
-    let profits = model
-        .add_table_1d("profits", instance.profits.clone())
-        .unwrap();
-    let weights = model
-        .add_table_1d("weights", instance.weights.clone())
-        .unwrap();
+    let profits = model.add_table_1d("profits", core.profits.clone()).unwrap();
+    let weights = model.add_table_1d("weights", core.weights.clone()).unwrap();
 
     let mut pack = Transition::new("pack");
     pack.set_cost(profits.element(current) + IntegerExpression::Cost);
@@ -59,14 +70,13 @@ fn main() {
     model.add_forward_transition(ignore).unwrap();
 
     model
-        .add_base_case(vec![Condition::comparison_e(
-            ComparisonOperator::Eq,
-            current,
-            n,
-        )])
+        .add_base_case_with_cost(
+            vec![Condition::comparison_e(ComparisonOperator::Eq, current, n)],
+            fixed_profit,
+        )
         .unwrap();
 
-    let mut total_profit_after = instance
+    let mut total_profit_after = core
         .profits
         .iter()
         .rev()
@@ -79,10 +89,10 @@ fn main() {
     total_profit_after.reverse();
     total_profit_after.push(0);
 
-    let mut ms = instance
+    let mut ms = core
         .profits
         .iter()
-        .zip(instance.weights.iter())
+        .zip(core.weights.iter())
         .map(|(&p, &w)| p as f64 / w as f64 + args.epsilon)
         .rev()
         .scan(0.0, |acc, x| {
@@ -97,14 +107,14 @@ fn main() {
     ms.push(0.0);
     let ms = model.add_table_1d("ms", ms).unwrap();
     model
-        .add_dual_bound(IntegerExpression::floor(remaining * ms.element(current)))
+        .add_dual_bound(IntegerExpression::floor(remaining * ms.element(current)) + fixed_profit)
         .unwrap();
 
     let total_profit_after = model
         .add_table_1d("total_profit_after", total_profit_after)
         .unwrap();
     model
-        .add_dual_bound(total_profit_after.element(current))
+        .add_dual_bound(total_profit_after.element(current) + fixed_profit)
         .unwrap();
 
     let model = Rc::new(model);
@@ -152,6 +162,11 @@ fn main() {
                 }
             })
             .collect::<Vec<_>>();
+        let packed_items = if let Some(reduction) = &reduction {
+            reduction.restore_solution(&packed_items)
+        } else {
+            packed_items
+        };
         instance.print_solution(&packed_items);
 
         if instance.validate(&packed_items, profit) {
@@ -13,12 +13,13 @@ static GLOBAL: Jemalloc = Jemalloc;
 
 struct Knapsack {
     instance: Instance,
+    fixed_profit: i32,
     total_profit_after: Vec<i32>,
     max_efficiency_after: Vec<f64>,
 }
 
 impl Knapsack {
-    fn new(instance: Instance, epsilon: f64) -> Self {
+    fn new(instance: Instance, fixed_profit: i32, epsilon: f64) -> Self {
         let mut total_profit_after = instance
             .profits
             .iter()
@@ -49,6 +50,7 @@ impl Knapsack {
 
         Self {
             instance,
+            fixed_profit,
             total_profit_after,
             max_efficiency_after,
         }
@@ -98,7 +100,7 @@ impl Dp for Knapsack {
 
     fn get_base_cost(&self, state: &Self::State) -> Option<Self::CostType> {
         if state.current == self.instance.profits.len() {
-            Some(0)
+            Some(self.fixed_profit)
         } else {
             None
         }
@@ -128,7 +130,7 @@ impl Bound for Knapsack {
 
     fn get_dual_bound(&self, state: &Self::State) -> Option<Self::CostType> {
         if state.current == self.instance.profits.len() {
-            return Some(0);
+            return Some(self.fixed_profit);
         }
 
         let maximum_total_profit = self.total_profit_after[state.current];
@@ -136,7 +138,7 @@ impl Bound for Knapsack {
         let maximum_efficiency_bound =
             (state.remaining as f64 * self.max_efficiency_after[state.current]).floor() as i32;
 
-        Some(cmp::min(maximum_total_profit, maximum_efficiency_bound))
+        Some(cmp::min(maximum_total_profit, maximum_efficiency_bound) + self.fixed_profit)
     }
 }
 
@@ -145,7 +147,23 @@ fn main() {
     let args = Args::parse();
 
     let instance = Instance::read_from_file(&args.input_file).unwrap();
-    let knapsack = Knapsack::new(instance.clone(), args.epsilon);
+    let reduction = if args.reduce {
+        let reduction = instance.reduce();
+        reduction.print_report();
+
+        Some(reduction)
+    } else {
+        None
+    };
+    let knapsack = if let Some(reduction) = &reduction {
+        Knapsack::new(
+            reduction.instance.clone(),
+            reduction.fixed_profit,
+            args.epsilon,
+        )
+    } else {
+        Knapsack::new(instance.clone(), 0, args.epsilon)
+    };
 
     let parameters = SearchParameters {
         time_limit: Some(args.time_limit),
@@ -174,6 +192,11 @@ fn main() {
             .enumerate()
             .filter_map(|(i, &x)| if x == 0 { Some(i) } else { None })
             .collect::<Vec<_>>();
+        let packed_items = if let Some(reduction) = &reduction {
+            reduction.restore_solution(&packed_items)
+        } else {
+            packed_items
+        };
         instance.print_solution(&packed_items);
 
         if instance.validate(&packed_items, profit) {
@@ -1,7 +1,9 @@
 use clap::{Parser, ValueEnum};
 use rpid::io;
+use std::cmp;
 use std::error::Error;
 use std::fs;
+use std::iter;
 
 #[derive(Clone, Debug)]
 pub struct Instance {
@@ -26,17 +28,24 @@ impl Instance {
         let matrix = io::read_matrix(&mut digits, n, 2)?;
         let profits = matrix.iter().map(|x| x[0]).collect::<Vec<_>>();
         let weights = matrix.iter().map(|x| x[1]).collect::<Vec<_>>();
-        let mut indices = (0..n).collect::<Vec<_>>();
+
+        Ok(Self::new(&profits, &weights, capacity))
+    }
+
+    /// Creates an instance with the items sorted in the descending order of efficiency,
+    /// where `indices` maps each position back to the given order.
+    pub fn new(profits: &[i32], weights: &[i32], capacity: i32) -> Self {
+        let mut indices = (0..profits.len()).collect::<Vec<_>>();
         indices.sort_by_key(|&i| (weights[i] as f64 / profits[i] as f64).to_bits());
         let profits = indices.iter().map(|&i| profits[i]).collect();
         let weights = indices.iter().map(|&i| weights[i]).collect();
 
-        Ok(Self {
+        Self {
             profits,
             weights,
             capacity,
             indices,
-        })
+        }
     }
 
     pub fn validate(&self, solution: &[usize], profit: i32) -> bool {
@@ -76,6 +85,191 @@ impl Instance {
 
         println!("Packed Items: {}", solution_indices);
     }
+
+    /// Fixes variables using the Dantzig bound and returns the problem over the remaining items.
+    ///
+    /// A variable is fixed if the Dantzig bound with the opposite value is less than the profit
+    /// of the greedy solution, so all optimal solutions remain in the reduced problem.
+    /// The remaining items form a core around the break item.
+    pub fn reduce(&self) -> Reduction {
+        let n = self.profits.len();
+        let capacity = self.capacity as i64;
+
+        let mut greedy_profit = 0;
+        let mut greedy_remaining = capacity;
+
+        for (&p, &w) in self.profits.iter().zip(self.weights.iter()) {
+            if w as i64 <= greedy_remaining {
+                greedy_remaining -= w as i64;
+                greedy_profit += p as i64;
+            }
+        }
+
+        let prefix_weights = iter::once(0)
+            .chain(self.weights.iter().scan(0, |acc, &w| {
+                *acc += w as i64;
+
+                Some(*acc)
+            }))
+            .collect::<Vec<_>>();
+        let prefix_profits = iter::once(0)
+            .chain(self.profits.iter().scan(0, |acc, &p| {
+                *acc += p as i64;
+
+                Some(*acc)
+            }))
+            .collect::<Vec<_>>();
+        let dantzig_bound = |capacity, excluded| {
+            self.compute_dantzig_bound(capacity, excluded, &prefix_weights, &prefix_profits)
+        };
+
+        let (break_item, upper_bound) = dantzig_bound(capacity, None).unwrap();
+
+        let mut items = vec![];
+        let mut fixed_to_one = vec![];
+        let mut fixed_to_zero = vec![];
+
+        for j in 0..n {
+            let bound_with_one = dantzig_bound(capacity - self.weights[j] as i64, Some(j))
+                .map(|(_, bound)| bound + self.profits[j] as i64);
+
+            if bound_with_one.is_none_or(|bound| bound < greedy_profit) {
+                fixed_to_zero.push(j);
+
+                continue;
+            }
+
+            let (_, bound_with_zero) = dantzig_bound(capacity, Some(j)).unwrap();
+
+            if bound_with_zero < greedy_profit {
+                fixed_to_one.push(j);
+            } else {
+                items.push(j);
+            }
+        }
+
+        // The break item is kept if all variables are fixed so that the reduced instance is not empty.
+        if items.is_empty() && n > 0 {
+            let j = cmp::min(break_item, n - 1);
+            fixed_to_one.retain(|&i| i != j);
+            fixed_to_zero.retain(|&i| i != j);
+            items.push(j);
+        }
+
+        let fixed_profit = fixed_to_one.iter().map(|&i| self.profits[i]).sum::<i32>();
+        let fixed_weight = fixed_to_one.iter().map(|&i| self.weights[i]).sum::<i32>();
+        let instance = Self {
+            profits: items.iter().map(|&i| self.profits[i]).collect(),
+            weights: items.iter().map(|&i| self.weights[i]).collect(),
+            capacity: self.capacity - fixed_weight,
+            indices: items.iter().map(|&i| self.indices[i]).collect(),
+        };
+
+        Reduction {
+            instance,
+            items,
+            fixed_to_one,
+            fixed_to_zero,
+            fixed_profit,
+            break_item,
+            lower_bound: greedy_profit as i32,
+            upper_bound: upper_bound as i32,
+        }
+    }
+
+    // Returns the break item and the Dantzig bound without the excluded item,
+    // or `None` if the capacity is negative.
+    //
+    // The break item is found by binary search over the prefix sums of the weights.
+    fn compute_dantzig_bound(
+        &self,
+        capacity: i64,
+        excluded: Option<usize>,
+        prefix_weights: &[i64],
+        prefix_profits: &[i64],
+    ) -> Option<(usize, i64)> {
+        if capacity < 0 {
+            return None;
+        }
+
+        let n = self.profits.len();
+        let without_excluded = |prefix: &[i64], values: &[i32], k: usize| match excluded {
+            Some(j) if j < k => prefix[k] - values[j] as i64,
+            _ => prefix[k],
+        };
+
+        // The total weight of the first `k` items is non-decreasing in `k`.
+        let mut lo = 0;
+        let mut hi = n;
+
+        while lo < hi {
+            let mid = (lo + hi).div_ceil(2);
+
+            if without_excluded(prefix_weights, &self.weights, mid) <= capacity {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+
+        let profit = without_excluded(prefix_profits, &self.profits, lo);
+        let remaining = capacity - without_excluded(prefix_weights, &self.weights, lo);
+
+        // The break item is not the excluded one since excluding it does not increase the total weight.
+        if lo < n {
+            // Since the profits and weights are integers, the fractional part is truncated exactly.
+            let bound = profit + remaining * self.profits[lo] as i64 / self.weights[lo] as i64;
+
+            Some((lo, bound))
+        } else {
+            Some((n, profit))
+        }
+    }
+}
+
+/// Result of fixing variables of a knapsack instance.
+///
+/// Items are identified by their positions in the original instance.
+#[derive(Clone, Debug)]
+pub struct Reduction {
+    /// Instance over the remaining items with the capacity left by the items fixed to one.
+    pub instance: Instance,
+    /// Original positions of the items in the reduced instance.
+    pub items: Vec<usize>,
+    pub fixed_to_one: Vec<usize>,
+    pub fixed_to_zero: Vec<usize>,
+    pub fixed_profit: i32,
+    pub break_item: usize,
+    pub lower_bound: i32,
+    pub upper_bound: i32,
+}
+
+impl Reduction {
+    /// Maps packed items of the reduced instance back to the original instance,
+    /// adding the items fixed to one.
+    pub fn restore_solution(&self, solution: &[usize]) -> Vec<usize> {
+        let mut solution = solution
+            .iter()
+            .map(|&i| self.items[i])
+            .chain(self.fixed_to_one.iter().copied())
+            .collect::<Vec<_>>();
+        solution.sort();
+
+        solution
+    }
+
+    pub fn print_report(&self) {
+        println!("Greedy lower bound: {}", self.lower_bound);
+        println!("Dantzig upper bound: {}", self.upper_bound);
+        println!("Break item: {}", self.break_item);
+        println!(
+            "Items fixed to one: {} (profit {})",
+            self.fixed_to_one.len(),
+            self.fixed_profit
+        );
+        println!("Items fixed to zero: {}", self.fixed_to_zero.len());
+        println!("Items in the core: {}", self.items.len());
+    }
 }
 
 #[derive(Debug, Clone, ValueEnum)]
@@ -101,4 +295,110 @@ pub struct Args {
         help = "Threshold for floating point values"
     )]
     pub epsilon: f64,
+    #[arg(
+        long,
+        action,
+        help = "Fixes variables using the Dantzig bound and solves the core problem"
+    )]
+    pub reduce: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Generates a random instance with a linear congruential generator.
+    fn generate_instance(seed: u64, n: usize) -> Instance {
+        let mut state = seed;
+        let mut next = |lb: i32, ub: i32| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+
+            lb + ((state >> 33) % (ub - lb + 1) as u64) as i32
+        };
+
+        let weights = (0..n).map(|_| next(1, 30)).collect::<Vec<_>>();
+        // Correlated profits make ties in efficiency and variables that are not fixed.
+        let profits = weights.iter().map(|&w| w + next(0, 10)).collect::<Vec<_>>();
+        let capacity = next(0, weights.iter().sum::<i32>() + 10);
+
+        Instance::new(&profits, &weights, capacity)
+    }
+
+    // Returns the maximum profit and the packed items by enumerating all subsets.
+    fn solve_by_brute_force(instance: &Instance) -> (i32, Vec<usize>) {
+        let n = instance.profits.len();
+
+        (0..1u32 << n)
+            .map(|subset| {
+                (0..n)
+                    .filter(|&i| subset & (1 << i) != 0)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|items| {
+                items.iter().map(|&i| instance.weights[i]).sum::<i32>() <= instance.capacity
+            })
+            .map(|items| (items.iter().map(|&i| instance.profits[i]).sum(), items))
+            .max_by_key(|(profit, _)| *profit)
+            .unwrap()
+    }
+
+    #[test]
+    fn reduce_keeps_optimal_solutions() {
+        for seed in 0..200 {
+            let instance = generate_instance(seed, 1 + seed as usize % 12);
+            let (optimal_profit, _) = solve_by_brute_force(&instance);
+            let reduction = instance.reduce();
+
+            assert!(reduction.lower_bound <= optimal_profit);
+            assert!(optimal_profit <= reduction.upper_bound);
+
+            let (core_profit, core_solution) = solve_by_brute_force(&reduction.instance);
+            assert_eq!(core_profit + reduction.fixed_profit, optimal_profit);
+
+            let solution = reduction.restore_solution(&core_solution);
+            assert!(instance.validate(&solution, optimal_profit));
+        }
+    }
+
+    #[test]
+    fn restore_solution_maps_core_items_to_original_positions() {
+        for seed in 0..50 {
+            let instance = generate_instance(seed, 12);
+            let reduction = instance.reduce();
+            let n = instance.profits.len();
+
+            let mut positions = reduction
+                .items
+                .iter()
+                .chain(reduction.fixed_to_one.iter())
+                .chain(reduction.fixed_to_zero.iter())
+                .copied()
+                .collect::<Vec<_>>();
+            positions.sort();
+            assert_eq!(positions, (0..n).collect::<Vec<_>>());
+
+            for (k, &i) in reduction.items.iter().enumerate() {
+                assert_eq!(reduction.instance.profits[k], instance.profits[i]);
+                assert_eq!(reduction.instance.weights[k], instance.weights[i]);
+                assert_eq!(reduction.instance.indices[k], instance.indices[i]);
+            }
+
+            let core_solution = (0..reduction.items.len()).collect::<Vec<_>>();
+            let mut expected = reduction
+                .items
+                .iter()
+                .chain(reduction.fixed_to_one.iter())
+                .copied()
+                .collect::<Vec<_>>();
+            expected.sort();
+            assert_eq!(reduction.restore_solution(&core_solution), expected);
+            assert_eq!(reduction.restore_solution(&[]), {
+                let mut fixed_to_one = reduction.fixed_to_one.clone();
+                fixed_to_one.sort();
+                fixed_to_one
+            });
+        }
+    }
 }
@@ -23,7 +23,13 @@ fn main() {
     let filepath = args.input_file;
 
     let instance = Instance::load(&filepath).unwrap();
-    let instance = RoundedInstance::try_from(instance).unwrap();
+    let instance = RoundedInstance::new(instance, args.single_commodity).unwrap();
+
+    if let Some(filename) = &args.dump_preprocessing {
+        let graph = instance.extract_precedence_graph();
+        graph.print_summary();
+        graph.dump(filename).unwrap();
+    }
 
     let mut model = Model::default();
 
@@ -40,6 +46,20 @@ fn main() {
     let load = model
         .add_integer_resource_variable("load", true, 0)
         .unwrap();
+    // In the single-commodity case, the vehicle can start with any load,
+    // so the range of the load must be within the capacity.
+    let load_range = if instance.single_commodity {
+        let min_load = model
+            .add_integer_resource_variable("min_load", false, 0)
+            .unwrap();
+        let max_load = model
+            .add_integer_resource_variable("max_load", true, 0)
+            .unwrap();
+
+        Some((min_load, max_load))
+    } else {
+        None
+    };
 
     let demands = instance
         .demands
@@ -83,13 +103,26 @@ fn main() {
         let new_load = load + d;
         visit.add_effect(load, new_load.clone()).unwrap();
 
+        if let Some((min_load, max_load)) = load_range {
+            let new_min_load = min_load.min(new_load.clone());
+            visit.add_effect(min_load, new_min_load.clone()).unwrap();
+            let new_max_load = max_load.max(new_load.clone());
+            visit.add_effect(max_load, new_max_load.clone()).unwrap();
+            visit.add_precondition(Condition::comparison_i(
+                ComparisonOperator::Le,
+                new_max_load - new_min_load,
+                instance.capacity,
+            ));
+        } else {
+            visit.add_precondition(Condition::comparison_i(
+                ComparisonOperator::Le,
+                new_load,
+                instance.capacity,
+            ));
+        }
+
         visit.add_precondition(connected.element(current, next));
         visit.add_precondition(unvisited.contains(next));
-        visit.add_precondition(Condition::comparison_i(
-            ComparisonOperator::Le,
-            new_load,
-            instance.capacity,
-        ));
         visit.add_precondition((unvisited & predecessors[next].clone()).is_empty());
 
         model.add_forward_transition(visit).unwrap();
@@ -3,7 +3,9 @@ use fixedbitset::FixedBitSet;
 use m_pdtsp::{Args, RoundedInstance, SolverChoice};
 use rpid::prelude::*;
 use rpid::{algorithms, io, solvers, timer::Timer};
-use std::cmp::Ordering;
+use std::cell::Cell;
+use std::cmp::{self, Ordering};
+use std::rc::Rc;
 use tsplib_parser::Instance;
 
 #[cfg(not(target_env = "msvc"))]
@@ -15,6 +17,7 @@ static GLOBAL: Jemalloc = Jemalloc;
 
 struct OnePdtsp {
     capacity: i32,
+    single_commodity: bool,
     demands: Vec<i32>,
     predecessors: Vec<FixedBitSet>,
     distances: Vec<Vec<Option<i32>>>,
@@ -42,6 +45,7 @@ impl From<RoundedInstance> for OnePdtsp {
 
         Self {
             capacity: instance.capacity,
+            single_commodity: instance.single_commodity,
             demands,
             predecessors,
             distances,
@@ -56,6 +60,8 @@ struct OnePdtspState {
     unvisited: FixedBitSet,
     current: usize,
     load: i32,
+    min_load: i32,
+    max_load: i32,
 }
 
 impl Dp for OnePdtsp {
@@ -71,6 +77,8 @@ impl Dp for OnePdtsp {
             unvisited,
             current: 0,
             load: 0,
+            min_load: 0,
+            max_load: 0,
         }
     }
 
@@ -81,14 +89,29 @@ impl Dp for OnePdtsp {
         state.unvisited.ones().filter_map(|next| {
             if let Some(d) = self.distances[state.current][next] {
                 let load = state.load + self.demands[next];
+                // The load never becomes negative with precedence of pickups and deliveries.
+                // In the single-commodity case, the vehicle can start with any load,
+                // so the range of the load must be within the capacity.
+                let (min_load, max_load) = if self.single_commodity {
+                    (
+                        cmp::min(state.min_load, load),
+                        cmp::max(state.max_load, load),
+                    )
+                } else {
+                    (0, load)
+                };
 
-                if load <= self.capacity && state.unvisited.is_disjoint(&self.predecessors[next]) {
+                if max_load - min_load <= self.capacity
+                    && state.unvisited.is_disjoint(&self.predecessors[next])
+                {
                     let mut unvisited = state.unvisited.clone();
                     unvisited.remove(next);
                     let successor = OnePdtspState {
                         unvisited,
                         current: next,
                         load,
+                        min_load,
+                        max_load,
                     };
 
                     Some((successor, d, next))
@@ -119,7 +142,16 @@ impl Dominance for OnePdtsp {
     }
 
     fn compare(&self, a: &Self::State, b: &Self::State) -> Option<Ordering> {
-        Some(b.load.cmp(&a.load))
+        // The load is determined by the visited nodes, so only its range matters.
+        if a.min_load == b.min_load && a.max_load == b.max_load {
+            Some(Ordering::Equal)
+        } else if a.min_load >= b.min_load && a.max_load <= b.max_load {
+            Some(Ordering::Greater)
+        } else if a.min_load <= b.min_load && a.max_load >= b.max_load {
+            Some(Ordering::Less)
+        } else {
+            None
+        }
     }
 }
 
@@ -171,7 +203,13 @@ fn main() {
     let filepath = args.input_file;
 
     let instance = Instance::load(&filepath).unwrap();
-    let instance = RoundedInstance::try_from(instance).unwrap();
+    let instance = RoundedInstance::new(instance, args.single_commodity).unwrap();
+
+    if let Some(filename) = &args.dump_preprocessing {
+        let graph = instance.extract_precedence_graph();
+        graph.print_summary();
+        graph.dump(filename).unwrap();
+    }
     let one_pdtsp = OnePdtsp::from(instance.clone());
 
     let parameters = SearchParameters {
@@ -179,6 +217,25 @@ fn main() {
         ..Default::default()
     };
     let solution = match args.solver {
+        SolverChoice::Cabs if args.local_search => {
+            let cabs_parameters = CabsParameters::default();
+            let primal_bound = Rc::new(Cell::new(None));
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let mut solver = io_util::create_cabs_with_shared_primal_bound(
+                one_pdtsp,
+                parameters,
+                cabs_parameters,
+                primal_bound.clone(),
+            );
+            io_util::run_solver_with_local_search_and_dump_solution_history(
+                &mut solver,
+                &args.history,
+                OptimizationMode::Minimization,
+                &primal_bound,
+                |tour| instance.improve_tour(tour),
+            )
+            .unwrap()
+        }
         SolverChoice::Cabs => {
             let cabs_parameters = CabsParameters::default();
             println!("Preparing time: {}s", timer.get_elapsed_time());
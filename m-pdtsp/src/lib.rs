@@ -1,6 +1,8 @@
 use clap::{Parser, ValueEnum};
 use fixedbitset::FixedBitSet;
+use io_util::PrecedenceGraph;
 use itertools::Itertools;
+use std::cmp;
 use std::error::Error;
 use tsplib_parser::Instance;
 
@@ -11,12 +13,25 @@ pub struct RoundedInstance {
     pub demands: Vec<Vec<i32>>,
     pub capacity: i32,
     pub distances: Vec<Vec<Option<i32>>>,
+    pub single_commodity: bool,
 }
 
 impl TryFrom<Instance> for RoundedInstance {
     type Error = Box<dyn Error>;
 
     fn try_from(instance: Instance) -> Result<Self, Box<dyn Error>> {
+        Self::new(instance, false)
+    }
+}
+
+impl RoundedInstance {
+    /// Creates an m-PDTSP instance, or a single-commodity instance (1-PDTSP) if `single_commodity`.
+    ///
+    /// In the m-PDTSP, each commodity is picked up at one node and delivered to another node.
+    /// In the 1-PDTSP, the single commodity is picked up and delivered at any nodes,
+    /// and the vehicle can leave the start with any load,
+    /// so the range of the load along the tour must not exceed the capacity.
+    pub fn new(instance: Instance, single_commodity: bool) -> Result<Self, Box<dyn Error>> {
         let distances = instance.get_full_distance_matrix()?;
         let distances = distances
             .into_iter()
@@ -38,50 +53,126 @@ impl TryFrom<Instance> for RoundedInstance {
         let demands = demands.into_iter().map(|(_, d)| d).collect();
         let capacity = instance.capacity.ok_or("Capacity not defined")?;
 
-        Ok(Self {
+        let instance = Self {
             nodes,
             demand_dimension,
             demands,
             capacity,
             distances,
+            single_commodity,
+        };
+
+        if single_commodity && demand_dimension > 1 {
+            return Err("A single-commodity instance must have only one demand dimension".into());
+        }
+
+        if !single_commodity && instance.has_unpaired_commodity() {
+            return Err(
+                "Each commodity must be picked up at one node and delivered to another node".into(),
+            );
+        }
+
+        Ok(instance)
+    }
+
+    /// Checks if some commodity is not picked up at exactly one node
+    /// and delivered in the same amount to exactly one other node.
+    fn has_unpaired_commodity(&self) -> bool {
+        (0..self.demand_dimension).any(|k| {
+            let pickups = self.demands.iter().filter(|d| d[k] > 0).collect_vec();
+            let deliveries = self.demands.iter().filter(|d| d[k] < 0).collect_vec();
+
+            pickups.len() > 1 || deliveries.len() > 1 || pickups.len() != deliveries.len() || {
+                pickups
+                    .iter()
+                    .zip(deliveries.iter())
+                    .any(|(p, d)| p[k] != -d[k])
+            }
         })
     }
-}
 
-impl RoundedInstance {
     pub fn validate(&self, tours: &[usize], cost: i32) -> bool {
+        let Some(recomputed_cost) = self.evaluate_tour(tours, true) else {
+            return false;
+        };
+
+        if cost != recomputed_cost {
+            println!("Invalid cost: {} != {}", cost, recomputed_cost);
+
+            return false;
+        }
+
+        true
+    }
+
+    /// Returns the cost of a tour, or `None` if the tour is infeasible.
+    fn evaluate_tour(&self, tours: &[usize], verbose: bool) -> Option<i32> {
         let n = self.nodes.len();
 
         if tours.len() != n - 2 {
-            println!("Invalid tour length: {} != {}", tours.len(), n - 2);
+            if verbose {
+                println!("Invalid tour length: {} != {}", tours.len(), n - 2);
+            }
 
-            return false;
+            return None;
         }
 
         let mut visited = vec![false; self.nodes.len()];
         let mut current = 0;
         let mut loads = vec![0; self.demand_dimension];
         let mut recomputed_cost = 0;
+        let mut min_load = 0;
+        let mut max_load = 0;
 
         for &next in tours {
             if next >= self.nodes.len() - 1 {
-                println!("Invalid node index: {}", next);
+                if verbose {
+                    println!("Invalid node index: {}", next);
+                }
 
-                return false;
+                return None;
             }
 
             if visited[next] {
-                println!("Visited node twice: {}", next);
+                if verbose {
+                    println!("Visited node twice: {}", next);
+                }
 
-                return false;
+                return None;
             }
 
             if let Some(d) = self.distances[current][next] {
                 recomputed_cost += d;
             } else {
-                println!("{} is not connected to {}", current, next);
+                if verbose {
+                    println!("{} is not connected to {}", current, next);
+                }
 
-                return false;
+                return None;
+            }
+
+            if self.single_commodity {
+                loads[0] += self.demands[next][0];
+                min_load = cmp::min(min_load, loads[0]);
+                max_load = cmp::max(max_load, loads[0]);
+
+                if max_load - min_load > self.capacity {
+                    if verbose {
+                        println!(
+                            "Capacity violation: load range {} > {} at {}",
+                            max_load - min_load,
+                            self.capacity,
+                            next
+                        );
+                    }
+
+                    return None;
+                }
+
+                visited[next] = true;
+                current = next;
+
+                continue;
             }
 
             let mut total_load = 0;
@@ -90,21 +181,25 @@ impl RoundedInstance {
                 *l += d;
 
                 if *l < 0 {
-                    println!("Negative load {} in dimension {} at {}", l, i, next);
+                    if verbose {
+                        println!("Negative load {} in dimension {} at {}", l, i, next);
+                    }
 
-                    return false;
+                    return None;
                 }
 
                 total_load += *l;
             }
 
             if total_load > self.capacity {
-                println!(
-                    "Capacity violation: {} > {} at {}",
-                    total_load, self.capacity, next
-                );
+                if verbose {
+                    println!(
+                        "Capacity violation: {} > {} at {}",
+                        total_load, self.capacity, next
+                    );
+                }
 
-                return false;
+                return None;
             }
 
             visited[next] = true;
@@ -116,18 +211,21 @@ impl RoundedInstance {
         if let Some(d) = self.distances[current][goal] {
             recomputed_cost += d;
         } else {
-            println!("{} is not connected to {}", current, goal);
+            if verbose {
+                println!("{} is not connected to {}", current, goal);
+            }
 
-            return false;
+            return None;
         }
 
-        if cost != recomputed_cost {
-            println!("Invalid cost: {} != {}", cost, recomputed_cost);
-
-            return false;
-        }
+        Some(recomputed_cost)
+    }
 
-        true
+    /// Improves a tour by 2-opt and or-opt moves keeping the loads feasible.
+    ///
+    /// Returns the improved cost and tour if an improvement is found.
+    pub fn improve_tour(&self, tour: &[usize]) -> Option<(i32, Vec<usize>)> {
+        io_util::improve_sequence_by_local_search(tour, |tour| self.evaluate_tour(tour, false))
     }
 
     pub fn print_solution(&self, tour: &[usize]) {
@@ -140,9 +238,23 @@ impl RoundedInstance {
         );
     }
 
+    /// Returns the predecessors of each node and the distances without edges
+    /// that cannot be used in a feasible tour.
+    ///
+    /// Precedence is not inferred for a single-commodity instance,
+    /// where edges are filtered only by the range of the load.
     pub fn extract_predecessors_and_filtered_distances(
         &self,
     ) -> (Vec<FixedBitSet>, Vec<Vec<Option<i32>>>) {
+        if self.single_commodity {
+            let n = self.nodes.len();
+
+            return (
+                vec![FixedBitSet::with_capacity(n); n],
+                self.filter_distances_by_load_range(),
+            );
+        }
+
         let commodity_edges = self.extract_commodity_edge();
         let (predecessors, successors, precedence_matrix) =
             self.extract_precedence(&commodity_edges);
@@ -157,6 +269,73 @@ impl RoundedInstance {
         (predecessors, filtered_distances)
     }
 
+    /// Returns the precedence graph given by the commodities
+    /// and the edges removed by `extract_predecessors_and_filtered_distances`.
+    pub fn extract_precedence_graph(&self) -> PrecedenceGraph {
+        let n = self.nodes.len();
+        let labels = self.nodes.iter().map(|i| i.to_string()).collect();
+
+        if self.single_commodity {
+            let removed_edges = self
+                .get_edges()
+                .filter(|&(i, j)| !self.check_edge_by_load_range(i, j))
+                .map(|(i, j)| (i, j, "load range"))
+                .collect();
+
+            return PrecedenceGraph {
+                labels,
+                arcs: vec![],
+                removed_edges,
+            };
+        }
+
+        let commodity_edges = self.extract_commodity_edge();
+        let arcs = (0..n)
+            .flat_map(|i| (0..n).map(move |j| (i, j)))
+            .filter(|&(i, j)| commodity_edges[i][j].is_some())
+            .map(|(i, j)| {
+                if i == 0 || j == n - 1 {
+                    (i, j, "depot")
+                } else {
+                    (i, j, "commodity")
+                }
+            })
+            .collect();
+        let (predecessors, successors, precedence_matrix) =
+            self.extract_precedence(&commodity_edges);
+        let not_inferred_commodity_edges = Self::extract_not_inferred_commodity_edges(
+            &predecessors,
+            &successors,
+            &commodity_edges,
+        );
+        let removed_edges = self
+            .get_edges()
+            .filter_map(|(i, j)| {
+                self.get_edge_removal_reason(
+                    i,
+                    j,
+                    &precedence_matrix,
+                    &not_inferred_commodity_edges,
+                )
+                .map(|reason| (i, j, reason))
+            })
+            .collect();
+
+        PrecedenceGraph {
+            labels,
+            arcs,
+            removed_edges,
+        }
+    }
+
+    fn get_edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.distances.iter().enumerate().flat_map(|(i, row)| {
+            row.iter()
+                .enumerate()
+                .filter_map(move |(j, d)| d.map(|_| (i, j)))
+        })
+    }
+
     fn extract_commodity_edge(&self) -> Vec<Vec<Option<i32>>> {
         let n = self.nodes.len();
         let mut edges = vec![vec![None; n]; n];
@@ -341,6 +520,51 @@ impl RoundedInstance {
         load_i + load_j <= self.capacity
     }
 
+    // Checks if the demands of two customers alone do not exceed the capacity range.
+    fn check_edge_by_load_range(&self, i: usize, j: usize) -> bool {
+        if i == 0 || j == self.nodes.len() - 1 {
+            return true;
+        }
+
+        let di = self.demands[i][0];
+        let dj = self.demands[j][0];
+        let range = cmp::max(cmp::max(0, di), di + dj) - cmp::min(cmp::min(0, di), di + dj);
+
+        range <= self.capacity
+    }
+
+    fn filter_distances_by_load_range(&self) -> Vec<Vec<Option<i32>>> {
+        self.distances
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(j, d)| d.filter(|_| self.check_edge_by_load_range(i, j)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Returns the reason why `j` cannot directly follow `i`, or `None` if it can.
+    fn get_edge_removal_reason(
+        &self,
+        i: usize,
+        j: usize,
+        precedence_matrix: &[Vec<bool>],
+        not_inferred_commodity_edges: &[Vec<Option<i32>>],
+    ) -> Option<&'static str> {
+        if precedence_matrix[j][i] {
+            Some("reverse precedence")
+        } else if precedence_matrix[i][j] && not_inferred_commodity_edges[i][j].is_none() {
+            Some("intermediate node")
+        } else if !self.check_edge_by_capacity(i, j, not_inferred_commodity_edges) {
+            Some("capacity")
+        } else {
+            None
+        }
+    }
+
     fn filter_distances(
         &self,
         precedence_matrix: &[Vec<bool>],
@@ -353,16 +577,14 @@ impl RoundedInstance {
                 row.iter()
                     .enumerate()
                     .map(|(j, d)| {
-                        d.and_then(|d| {
-                            if self.check_edge_by_capacity(i, j, not_inferred_commodity_edges)
-                                && !precedence_matrix[j][i]
-                                && (!precedence_matrix[i][j]
-                                    || not_inferred_commodity_edges[i][j].is_some())
-                            {
-                                Some(d)
-                            } else {
-                                None
-                            }
+                        d.filter(|_| {
+                            self.get_edge_removal_reason(
+                                i,
+                                j,
+                                precedence_matrix,
+                                not_inferred_commodity_edges,
+                            )
+                            .is_none()
                         })
                     })
                     .collect()
@@ -387,4 +609,21 @@ pub struct Args {
     pub history: String,
     #[arg(short, long, default_value_t = 1800.0, help = "Time limit")]
     pub time_limit: f64,
+    #[arg(
+        long,
+        action,
+        help = "Improve new solutions found by CABS with local search"
+    )]
+    pub local_search: bool,
+    #[arg(
+        long,
+        help = "Prefix of files to dump the inferred precedence graph in the DOT and JSON formats (<prefix>.dot and <prefix>.json)"
+    )]
+    pub dump_preprocessing: Option<String>,
+    #[arg(
+        long,
+        action,
+        help = "Solve the single-commodity variant (1-PDTSP), where the commodity is picked up and delivered at any nodes"
+    )]
+    pub single_commodity: bool,
 }
@@ -1,4 +1,5 @@
 use clap::Parser;
+use io_util::InstanceResult;
 use mdkp::{Args, Instance, SolverChoice};
 use rpid::prelude::*;
 use rpid::{algorithms, io, solvers, timer::Timer};
@@ -146,11 +147,8 @@ impl Bound for Mdkp {
     }
 }
 
-fn main() {
+fn solve(instance: Instance, args: &Args, history: &str) -> InstanceResult<i32> {
     let timer = Timer::default();
-    let args = Args::parse();
-
-    let instance = Instance::read_from_file(&args.input_file).unwrap();
     let mdkp = Mdkp::new(instance.clone(), args.epsilon);
 
     let parameters = SearchParameters {
@@ -163,17 +161,17 @@ fn main() {
             let cabs_parameters = CabsParameters::default();
             println!("Preparing time: {}s", timer.get_elapsed_time());
             let mut solver = solvers::create_cabs(mdkp, parameters, cabs_parameters);
-            io::run_solver_and_dump_solution_history(&mut solver, &args.history).unwrap()
+            io::run_solver_and_dump_solution_history(&mut solver, history).unwrap()
         }
         SolverChoice::Astar => {
             println!("Preparing time: {}s", timer.get_elapsed_time());
             let mut solver = solvers::create_astar(mdkp, parameters);
-            io::run_solver_and_dump_solution_history(&mut solver, &args.history).unwrap()
+            io::run_solver_and_dump_solution_history(&mut solver, history).unwrap()
         }
     };
     io::print_solution_statistics(&solution);
 
-    if let Some(profit) = solution.cost {
+    let is_valid = if let Some(profit) = solution.cost {
         let packed_items = solution
             .transitions
             .iter()
@@ -189,10 +187,30 @@ fn main() {
                 .join(" ")
         );
 
-        if instance.validate(&packed_items, profit) {
+        let is_valid = instance.validate(&packed_items, profit);
+
+        if is_valid {
             println!("The solution is valid.");
         } else {
             println!("The solution is invalid.");
         }
+
+        is_valid
+    } else {
+        false
+    };
+
+    InstanceResult {
+        cost: solution.cost,
+        bound: solution.best_bound,
+        is_optimal: solution.is_optimal,
+        is_valid,
+        time: solution.time,
     }
 }
+
+fn main() {
+    let args = Args::parse();
+
+    mdkp::solve_instances(&args, |instance, history| solve(instance, &args, history)).unwrap();
+}
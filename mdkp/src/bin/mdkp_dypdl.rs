@@ -4,6 +4,7 @@ use dypdl_heuristic_search::{
     create_caasdy, create_dual_bound_cabs, BeamSearchParameters, CabsParameters, FEvaluatorType,
     Parameters,
 };
+use io_util::InstanceResult;
 use mdkp::{Args, Instance, SolverChoice};
 use rpid::timer::Timer;
 use std::rc::Rc;
@@ -15,11 +16,8 @@ use tikv_jemallocator::Jemalloc;
 #[global_allocator]
 static GLOBAL: Jemalloc = Jemalloc;
 
-fn main() {
+fn solve(instance: Instance, args: &Args, history: &str) -> InstanceResult<i32> {
     let timer = Timer::default();
-    let args = Args::parse();
-
-    let instance = Instance::read_from_file(&args.input_file).unwrap();
 
     let mut model = Model::default();
     model.set_maximize();
@@ -94,6 +92,61 @@ fn main() {
     total_profit_after.reverse();
     total_profit_after.push(0);
 
+    // The capacity constraints are aggregated into a single knapsack constraint with surrogate multipliers.
+    // Items with zero surrogate weights are always packed in the bound.
+    let multipliers = instance.compute_surrogate_multipliers(args.epsilon);
+    let surrogate_weights = instance.get_surrogate_weights(&multipliers);
+    let mut surrogate_ms = surrogate_weights
+        .iter()
+        .zip(instance.profits.iter())
+        .map(|(&w, &p)| {
+            if w > 0.0 {
+                p as f64 / w + args.epsilon
+            } else {
+                0.0
+            }
+        })
+        .rev()
+        .scan(0.0, |acc, x| {
+            if *acc < x {
+                *acc = x;
+            }
+
+            Some(*acc)
+        })
+        .collect::<Vec<_>>();
+    surrogate_ms.reverse();
+    surrogate_ms.push(0.0);
+    let mut free_profit_after = surrogate_weights
+        .iter()
+        .zip(instance.profits.iter())
+        .map(|(&w, &p)| if w > 0.0 { 0 } else { p })
+        .rev()
+        .scan(0, |acc, x| {
+            *acc += x;
+
+            Some(*acc)
+        })
+        .collect::<Vec<_>>();
+    free_profit_after.reverse();
+    free_profit_after.push(0);
+    let surrogate_ms = model.add_table_1d("surrogate_ms", surrogate_ms).unwrap();
+    let free_profit_after = model
+        .add_table_1d("free_profit_after", free_profit_after)
+        .unwrap();
+    let surrogate_capacity = remaining
+        .iter()
+        .zip(multipliers)
+        .map(|(&r, u)| u * ContinuousExpression::from(r))
+        .reduce(|acc, x| acc + x)
+        .unwrap();
+    model
+        .add_dual_bound(
+            IntegerExpression::floor(surrogate_capacity * surrogate_ms.element(current))
+                + free_profit_after.element(current),
+        )
+        .unwrap();
+
     instance
         .weights
         .iter()
@@ -164,11 +217,10 @@ fn main() {
         }
     };
 
-    let solution =
-        io_util::run_solver_and_dump_solution_history(&mut solver, &args.history).unwrap();
+    let solution = io_util::run_solver_and_dump_solution_history(&mut solver, history).unwrap();
     io_util::print_solution_statistics(&solution);
 
-    if let Some(profit) = solution.cost {
+    let is_valid = if let Some(profit) = solution.cost {
         let packed_items = solution
             .transitions
             .iter()
@@ -190,10 +242,30 @@ fn main() {
                 .join(" ")
         );
 
-        if instance.validate(&packed_items, profit) {
+        let is_valid = instance.validate(&packed_items, profit);
+
+        if is_valid {
             println!("The solution is valid.");
         } else {
             println!("The solution is invalid.");
         }
+
+        is_valid
+    } else {
+        false
+    };
+
+    InstanceResult {
+        cost: solution.cost,
+        bound: solution.best_bound,
+        is_optimal: solution.is_optimal,
+        is_valid,
+        time: solution.time,
     }
 }
+
+fn main() {
+    let args = Args::parse();
+
+    mdkp::solve_instances(&args, |instance, history| solve(instance, &args, history)).unwrap();
+}
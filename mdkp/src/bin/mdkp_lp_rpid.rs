@@ -0,0 +1,247 @@
+use clap::Parser;
+use io_util::InstanceResult;
+use mdkp::{Args, Instance, LpSolution, LpTableau, SolverChoice};
+use rpid::prelude::*;
+use rpid::{io, solvers, timer::Timer};
+use std::rc::Rc;
+
+#[cfg(not(target_env = "msvc"))]
+use tikv_jemallocator::Jemalloc;
+
+#[cfg(not(target_env = "msvc"))]
+#[global_allocator]
+static GLOBAL: Jemalloc = Jemalloc;
+
+struct Mdkp {
+    instance: Instance,
+    epsilon: f64,
+}
+
+#[derive(Clone)]
+struct MdkpState {
+    current: usize,
+    remaining: Vec<i32>,
+    lp_bound: f64,
+    lp: Rc<LpSolution>,
+    lp_tableau: Rc<LpTableau>,
+}
+
+impl Mdkp {
+    fn new_state(&self, current: usize, remaining: Vec<i32>) -> MdkpState {
+        let tableau = self
+            .instance
+            .create_lp_tableau(current, &remaining, self.epsilon);
+
+        self.create_state(current, remaining, Rc::new(tableau))
+    }
+
+    // The bound is computed from the dual values, so it is admissible even with numerical errors in the tableau.
+    fn create_state(
+        &self,
+        current: usize,
+        remaining: Vec<i32>,
+        tableau: Rc<LpTableau>,
+    ) -> MdkpState {
+        let lp = tableau.get_solution(current, &self.instance.profits);
+        let lp_bound = self
+            .instance
+            .compute_dual_bound(current, &remaining, &lp.duals);
+
+        MdkpState {
+            current,
+            remaining,
+            lp_bound,
+            lp: Rc::new(lp),
+            lp_tableau: tableau,
+        }
+    }
+
+    // The LP solution of the parent is still optimal if the item takes the same value in it,
+    // and the bound with the same dual values is updated by removing the item.
+    // Otherwise, the item is fixed in the tableau of the parent, which is reoptimized by the dual simplex method.
+    fn get_successor(&self, state: &MdkpState, remaining: Vec<i32>, pack: bool) -> MdkpState {
+        let i = state.current;
+        let x = state.lp.x[i];
+
+        if (pack && x >= 1.0 - self.epsilon) || (!pack && x <= self.epsilon) {
+            let dual_weight = self
+                .instance
+                .weights
+                .iter()
+                .zip(state.lp.duals.iter())
+                .map(|(ws, &u)| u.max(0.0) * ws[i] as f64)
+                .sum::<f64>();
+            let reduced_profit = (self.instance.profits[i] as f64 - dual_weight).max(0.0);
+            let lp_bound = if pack {
+                state.lp_bound - reduced_profit - dual_weight
+            } else {
+                state.lp_bound - reduced_profit
+            };
+
+            MdkpState {
+                current: i + 1,
+                remaining,
+                lp_bound,
+                lp: state.lp.clone(),
+                lp_tableau: state.lp_tableau.clone(),
+            }
+        } else {
+            let value = if pack { 1.0 } else { 0.0 };
+
+            match state.lp_tableau.fix_item(i, value, self.epsilon) {
+                Some(tableau) => self.create_state(i + 1, remaining, Rc::new(tableau)),
+                None => self.new_state(i + 1, remaining),
+            }
+        }
+    }
+}
+
+impl Dp for Mdkp {
+    type State = MdkpState;
+    type CostType = i32;
+
+    fn get_target(&self) -> Self::State {
+        self.new_state(0, self.instance.capacities.clone())
+    }
+
+    fn get_successors(
+        &self,
+        state: &Self::State,
+    ) -> impl IntoIterator<Item = (Self::State, Self::CostType, usize)> {
+        let mut remaining = state.remaining.clone();
+
+        for (j, (&r, ws)) in state
+            .remaining
+            .iter()
+            .zip(self.instance.weights.iter())
+            .enumerate()
+        {
+            if ws[state.current] > r {
+                remaining[..j]
+                    .iter_mut()
+                    .zip(state.remaining[..j].iter())
+                    .for_each(|(x, y)| *x = *y);
+                let successor = self.get_successor(state, remaining, false);
+
+                return vec![(successor, 0, 1)];
+            }
+
+            remaining[j] -= ws[state.current];
+        }
+
+        let successor_1 = self.get_successor(state, remaining, true);
+        let successor_2 = self.get_successor(state, state.remaining.clone(), false);
+
+        vec![
+            (successor_1, self.instance.profits[state.current], 0),
+            (successor_2, 0, 1),
+        ]
+    }
+
+    fn get_base_cost(&self, state: &Self::State) -> Option<Self::CostType> {
+        if state.current == self.instance.profits.len() {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    fn get_optimization_mode(&self) -> OptimizationMode {
+        OptimizationMode::Maximization
+    }
+}
+
+impl Dominance for Mdkp {
+    type State = MdkpState;
+    type Key = (usize, Vec<i32>);
+
+    fn get_key(&self, state: &Self::State) -> Self::Key {
+        (state.current, state.remaining.clone())
+    }
+}
+
+impl Bound for Mdkp {
+    type State = MdkpState;
+    type CostType = i32;
+
+    fn get_dual_bound(&self, state: &Self::State) -> Option<Self::CostType> {
+        if state.current == self.instance.profits.len() {
+            return Some(0);
+        }
+
+        let bound = (state.lp_bound + self.epsilon).floor() as i32;
+
+        Some(bound)
+    }
+}
+
+fn solve(instance: Instance, args: &Args, history: &str) -> InstanceResult<i32> {
+    let timer = Timer::default();
+    let mdkp = Mdkp {
+        instance: instance.clone(),
+        epsilon: args.epsilon,
+    };
+
+    let parameters = SearchParameters {
+        time_limit: Some(args.time_limit),
+        ..Default::default()
+    };
+
+    let solution = match args.solver {
+        SolverChoice::Cabs => {
+            let cabs_parameters = CabsParameters::default();
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let mut solver = solvers::create_cabs(mdkp, parameters, cabs_parameters);
+            io::run_solver_and_dump_solution_history(&mut solver, history).unwrap()
+        }
+        SolverChoice::Astar => {
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let mut solver = solvers::create_astar(mdkp, parameters);
+            io::run_solver_and_dump_solution_history(&mut solver, history).unwrap()
+        }
+    };
+    io::print_solution_statistics(&solution);
+
+    let is_valid = if let Some(profit) = solution.cost {
+        let packed_items = solution
+            .transitions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &x)| if x == 0 { Some(i) } else { None })
+            .collect::<Vec<_>>();
+        println!(
+            "Packed items: {}",
+            packed_items
+                .iter()
+                .map(|&i| i.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+
+        let is_valid = instance.validate(&packed_items, profit);
+
+        if is_valid {
+            println!("The solution is valid.");
+        } else {
+            println!("The solution is invalid.");
+        }
+
+        is_valid
+    } else {
+        false
+    };
+
+    InstanceResult {
+        cost: solution.cost,
+        bound: solution.best_bound,
+        is_optimal: solution.is_optimal,
+        is_valid,
+        time: solution.time,
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+
+    mdkp::solve_instances(&args, |instance, history| solve(instance, &args, history)).unwrap();
+}
@@ -0,0 +1,231 @@
+use clap::Parser;
+use io_util::InstanceResult;
+use mdkp::{Args, Instance, SolverChoice};
+use rpid::prelude::*;
+use rpid::{algorithms, io, solvers, timer::Timer};
+
+#[cfg(not(target_env = "msvc"))]
+use tikv_jemallocator::Jemalloc;
+
+#[cfg(not(target_env = "msvc"))]
+#[global_allocator]
+static GLOBAL: Jemalloc = Jemalloc;
+
+struct Mdkp {
+    instance: Instance,
+    multipliers: Vec<f64>,
+    sorted_items: Vec<(usize, f64, i32)>,
+    epsilon: f64,
+}
+
+impl Mdkp {
+    fn new(instance: Instance, epsilon: f64) -> Self {
+        let multipliers = instance.compute_surrogate_multipliers(epsilon);
+        let surrogate_weights = instance.get_surrogate_weights(&multipliers);
+        let efficiency = |w: f64, p: i32| {
+            if w > 0.0 {
+                p as f64 / w
+            } else {
+                f64::INFINITY
+            }
+        };
+        let mut sorted_items = surrogate_weights
+            .into_iter()
+            .zip(instance.profits.iter().copied())
+            .enumerate()
+            .map(|(i, (w, p))| (i, w, p))
+            .collect::<Vec<_>>();
+        sorted_items.sort_by(|&(_, w1, p1), &(_, w2, p2)| {
+            efficiency(w2, p2).total_cmp(&efficiency(w1, p1))
+        });
+
+        Self {
+            instance,
+            multipliers,
+            sorted_items,
+            epsilon,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct MdkpState {
+    current: usize,
+    remaining: Vec<i32>,
+}
+
+impl Dp for Mdkp {
+    type State = MdkpState;
+    type CostType = i32;
+
+    fn get_target(&self) -> Self::State {
+        MdkpState {
+            current: 0,
+            remaining: self.instance.capacities.clone(),
+        }
+    }
+
+    fn get_successors(
+        &self,
+        state: &Self::State,
+    ) -> impl IntoIterator<Item = (Self::State, Self::CostType, usize)> {
+        let mut remaining = state.remaining.clone();
+
+        for (j, (&r, ws)) in state
+            .remaining
+            .iter()
+            .zip(self.instance.weights.iter())
+            .enumerate()
+        {
+            if ws[state.current] > r {
+                remaining[..j]
+                    .iter_mut()
+                    .zip(state.remaining[..j].iter())
+                    .for_each(|(x, y)| *x = *y);
+                let successor = MdkpState {
+                    current: state.current + 1,
+                    remaining,
+                };
+
+                return vec![(successor, 0, 1)];
+            }
+
+            remaining[j] -= ws[state.current];
+        }
+
+        let successor_1 = MdkpState {
+            current: state.current + 1,
+            remaining,
+        };
+        let successor_2 = MdkpState {
+            current: state.current + 1,
+            remaining: state.remaining.clone(),
+        };
+
+        vec![
+            (successor_1, self.instance.profits[state.current], 0),
+            (successor_2, 0, 1),
+        ]
+    }
+
+    fn get_base_cost(&self, state: &Self::State) -> Option<Self::CostType> {
+        if state.current == self.instance.profits.len() {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    fn get_optimization_mode(&self) -> OptimizationMode {
+        OptimizationMode::Maximization
+    }
+}
+
+impl Dominance for Mdkp {
+    type State = MdkpState;
+    type Key = MdkpState;
+
+    fn get_key(&self, state: &Self::State) -> Self::Key {
+        state.clone()
+    }
+}
+
+impl Bound for Mdkp {
+    type State = MdkpState;
+    type CostType = i32;
+
+    fn get_dual_bound(&self, state: &Self::State) -> Option<Self::CostType> {
+        if state.current == self.instance.profits.len() {
+            return Some(0);
+        }
+
+        // The capacity constraints are aggregated into a single knapsack constraint.
+        let capacity = state
+            .remaining
+            .iter()
+            .zip(self.multipliers.iter())
+            .map(|(&r, &u)| u * r as f64)
+            .sum::<f64>();
+        let bound = algorithms::compute_fractional_knapsack_profit(
+            capacity,
+            self.sorted_items.iter().filter_map(|&(i, w, p)| {
+                if i >= state.current {
+                    Some((w, p as f64))
+                } else {
+                    None
+                }
+            }),
+            self.epsilon,
+        ) as i32;
+
+        Some(bound)
+    }
+}
+
+fn solve(instance: Instance, args: &Args, history: &str) -> InstanceResult<i32> {
+    let timer = Timer::default();
+    let mdkp = Mdkp::new(instance.clone(), args.epsilon);
+
+    let parameters = SearchParameters {
+        time_limit: Some(args.time_limit),
+        ..Default::default()
+    };
+
+    let solution = match args.solver {
+        SolverChoice::Cabs => {
+            let cabs_parameters = CabsParameters::default();
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let mut solver = solvers::create_cabs(mdkp, parameters, cabs_parameters);
+            io::run_solver_and_dump_solution_history(&mut solver, history).unwrap()
+        }
+        SolverChoice::Astar => {
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let mut solver = solvers::create_astar(mdkp, parameters);
+            io::run_solver_and_dump_solution_history(&mut solver, history).unwrap()
+        }
+    };
+    io::print_solution_statistics(&solution);
+
+    let is_valid = if let Some(profit) = solution.cost {
+        let packed_items = solution
+            .transitions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &x)| if x == 0 { Some(i) } else { None })
+            .collect::<Vec<_>>();
+        println!(
+            "Packed items: {}",
+            packed_items
+                .iter()
+                .map(|&i| i.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+
+        let is_valid = instance.validate(&packed_items, profit);
+
+        if is_valid {
+            println!("The solution is valid.");
+        } else {
+            println!("The solution is invalid.");
+        }
+
+        is_valid
+    } else {
+        false
+    };
+
+    InstanceResult {
+        cost: solution.cost,
+        bound: solution.best_bound,
+        is_optimal: solution.is_optimal,
+        is_valid,
+        time: solution.time,
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+
+    mdkp::solve_instances(&args, |instance, history| solve(instance, &args, history)).unwrap();
+}
@@ -1,4 +1,5 @@
 use clap::{Parser, ValueEnum};
+use io_util::InstanceResult;
 use rpid::io;
 use std::error::Error;
 use std::fs;
@@ -8,13 +9,83 @@ pub struct Instance {
     pub profits: Vec<i32>,
     pub weights: Vec<Vec<i32>>,
     pub capacities: Vec<i32>,
+    /// Optimal or best-known profit given in the file, which is `None` if it is zero.
+    pub best_known: Option<i32>,
 }
 
 impl Instance {
+    /// Reads an instance from a file.
+    ///
+    /// The file gives the number of items, the dimension, and the best-known profit,
+    /// followed by the profits, the weights in each dimension, and the capacities.
     pub fn read_from_file(filename: &str) -> Result<Self, Box<dyn Error>> {
         let file = fs::read_to_string(filename)?;
         let mut digits = file.split_whitespace();
 
+        Self::read_from_digits(&mut digits)
+    }
+
+    /// Reads instances from a file in the format of `mknap1.txt` and `mknapcb*.txt` in the OR-Library.
+    ///
+    /// The file gives the number of instances followed by the instances in the format of `read_from_file`.
+    pub fn read_all_from_orlib_file(filename: &str) -> Result<Vec<Self>, Box<dyn Error>> {
+        let file = fs::read_to_string(filename)?;
+        let mut digits = file.split_whitespace();
+
+        let n_instances = digits
+            .next()
+            .ok_or("failed to parse the number of instances".to_owned())?
+            .parse::<usize>()?;
+
+        (0..n_instances)
+            .map(|_| Self::read_from_digits(&mut digits))
+            .collect()
+    }
+
+    /// Reads an instance from a file in the format of the Glover and Kochenberger instances.
+    ///
+    /// The file gives the dimension, the number of items, and the best-known profit,
+    /// followed by the profits and each constraint as the weights followed by the capacity.
+    pub fn read_from_gk_file(filename: &str) -> Result<Self, Box<dyn Error>> {
+        let file = fs::read_to_string(filename)?;
+        let mut digits = file.split_whitespace();
+
+        let m = digits
+            .next()
+            .ok_or("failed to parse the dimension".to_owned())?
+            .parse::<usize>()?;
+        let n = digits
+            .next()
+            .ok_or("failed to parse the number of items".to_owned())?
+            .parse::<usize>()?;
+        let best_known = digits
+            .next()
+            .ok_or("failed to parse the best-known profit".to_owned())?
+            .parse::<f64>()?
+            .round() as i32;
+        let profits = io::read_vector(&mut digits, n)?;
+        let constraints = io::read_matrix(&mut digits, m, n + 1)?;
+        let capacities = constraints.iter().map(|row| row[n]).collect();
+        let weights = constraints
+            .into_iter()
+            .map(|mut row| {
+                row.truncate(n);
+
+                row
+            })
+            .collect();
+
+        Ok(Self {
+            profits,
+            weights,
+            capacities,
+            best_known: Some(best_known).filter(|&b| b != 0),
+        })
+    }
+
+    fn read_from_digits<'a>(
+        digits: &mut impl Iterator<Item = &'a str>,
+    ) -> Result<Self, Box<dyn Error>> {
         let n = digits
             .next()
             .ok_or("failed to parse the number of items".to_owned())?
@@ -23,18 +94,57 @@ impl Instance {
             .next()
             .ok_or("failed to parse the dimension".to_owned())?
             .parse::<usize>()?;
-        digits.next();
-        let profits = io::read_vector(&mut digits, n)?;
-        let weights = io::read_matrix(&mut digits, m, n)?;
-        let capacities = io::read_vector(&mut digits, m)?;
+        let best_known = digits
+            .next()
+            .ok_or("failed to parse the best-known profit".to_owned())?
+            .parse::<f64>()?
+            .round() as i32;
+        let profits = io::read_vector(digits, n)?;
+        let weights = io::read_matrix(digits, m, n)?;
+        let capacities = io::read_vector(digits, m)?;
 
         Ok(Self {
             profits,
             weights,
             capacities,
+            best_known: Some(best_known).filter(|&b| b != 0),
         })
     }
 
+    /// Reads instances from a file in the given format and selects the instance with `index` starting from 1.
+    ///
+    /// All instances are selected if `index` is `None`.
+    pub fn load(
+        filename: &str,
+        format: &FileFormat,
+        index: Option<usize>,
+    ) -> Result<Vec<(usize, Self)>, Box<dyn Error>> {
+        let instances = match format {
+            FileFormat::Single => vec![Self::read_from_file(filename)?],
+            FileFormat::Orlib => Self::read_all_from_orlib_file(filename)?,
+            FileFormat::Gk => vec![Self::read_from_gk_file(filename)?],
+        };
+
+        if let Some(index) = index {
+            if index == 0 || index > instances.len() {
+                return Err(format!("instance {} is not in 1..={}", index, instances.len()).into());
+            }
+        }
+
+        Ok(instances
+            .into_iter()
+            .enumerate()
+            .map(|(i, instance)| (i + 1, instance))
+            .filter(|(i, _)| index.is_none_or(|index| index == *i))
+            .collect())
+    }
+
+    /// Returns the gap of a profit to the best-known profit in percent.
+    pub fn compute_gap(&self, profit: i32) -> Option<f64> {
+        self.best_known
+            .map(|best_known| 100.0 * (best_known - profit) as f64 / best_known as f64)
+    }
+
     pub fn validate(&self, solution: &[usize], profit: i32) -> bool {
         let m = self.capacities.len();
 
@@ -63,6 +173,450 @@ impl Instance {
     }
 }
 
+/// Solution of the LP relaxation of a multidimensional knapsack subproblem.
+#[derive(Clone, Debug)]
+pub struct LpSolution {
+    pub objective: f64,
+    /// Values of the items, which are zero for items not in the subproblem.
+    pub x: Vec<f64>,
+    /// Dual values of the capacity constraints.
+    pub duals: Vec<f64>,
+}
+
+/// Simplex tableau of the LP relaxation, kept to reoptimize it after fixing items.
+///
+/// Column `i < n` is item `i`, and column `n + j` is the slack of dimension `j`.
+/// The items not in the subproblem are fixed by setting their lower and upper bounds to the same value.
+#[derive(Clone, Debug)]
+pub struct LpTableau {
+    rows: Vec<Vec<f64>>,
+    values: Vec<f64>,
+    reduced_costs: Vec<f64>,
+    basis: Vec<usize>,
+    is_basic: Vec<bool>,
+    at_upper: Vec<bool>,
+    lower: Vec<f64>,
+    upper: Vec<f64>,
+}
+
+impl LpTableau {
+    /// Returns the solution of the subproblem with the items from `start`.
+    pub fn get_solution(&self, start: usize, profits: &[i32]) -> LpSolution {
+        let n = profits.len();
+        let mut x = (0..n)
+            .map(|c| {
+                if c < start {
+                    0.0
+                } else {
+                    self.get_nonbasic_value(c)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        for (&c, &value) in self.basis.iter().zip(self.values.iter()) {
+            if c >= start && c < n {
+                x[c] = value;
+            }
+        }
+
+        let objective = x
+            .iter()
+            .zip(profits.iter())
+            .map(|(&x, &p)| x * p as f64)
+            .sum();
+        let duals = self.reduced_costs[n..].iter().map(|&d| -d).collect();
+
+        LpSolution {
+            objective,
+            x,
+            duals,
+        }
+    }
+
+    /// Fixes item `i` to `value` and reoptimizes the LP by the dual simplex method.
+    ///
+    /// The items before `i` are fixed to their current values, which must be integral.
+    /// Since the reduced costs do not change, the basis stays dual feasible,
+    /// and only the pivots to restore the primal feasibility are needed.
+    /// Returns `None` if the LP becomes infeasible.
+    pub fn fix_item(&self, i: usize, value: f64, epsilon: f64) -> Option<Self> {
+        let mut tableau = self.clone();
+
+        for (r, &c) in self.basis.iter().enumerate().filter(|&(_, &c)| c < i) {
+            tableau.lower[c] = self.values[r].round();
+            tableau.upper[c] = self.values[r].round();
+        }
+
+        for c in (0..i).filter(|&c| !self.is_basic[c]) {
+            tableau.lower[c] = self.get_nonbasic_value(c);
+            tableau.upper[c] = self.get_nonbasic_value(c);
+        }
+
+        if !tableau.is_basic[i] {
+            let delta = value - tableau.get_nonbasic_value(i);
+
+            for (row, x) in tableau.rows.iter().zip(tableau.values.iter_mut()) {
+                *x -= row[i] * delta;
+            }
+        }
+
+        tableau.lower[i] = value;
+        tableau.upper[i] = value;
+        tableau.at_upper[i] = false;
+
+        if !tableau.run_dual_simplex(epsilon) {
+            return None;
+        }
+
+        // Pivots with small errors may leave a basis that is not dual feasible.
+        tableau.run_primal_simplex(epsilon);
+
+        Some(tableau)
+    }
+
+    fn get_nonbasic_value(&self, c: usize) -> f64 {
+        if self.at_upper[c] {
+            self.upper[c]
+        } else {
+            self.lower[c]
+        }
+    }
+
+    // A nonbasic column can move if it is not fixed.
+    fn is_movable(&self, c: usize) -> bool {
+        !self.is_basic[c] && self.upper[c] > self.lower[c]
+    }
+
+    // The Dantzig rule is used to select an entering variable,
+    // and Bland's rule is used after many iterations to avoid cycling.
+    fn run_primal_simplex(&mut self, epsilon: f64) {
+        let n_columns = self.reduced_costs.len();
+        let max_dantzig_iterations = 50 * n_columns;
+        let mut iterations = 0;
+
+        loop {
+            let mut candidates = (0..n_columns).filter(|&c| {
+                self.is_movable(c)
+                    && if self.at_upper[c] {
+                        self.reduced_costs[c] < -epsilon
+                    } else {
+                        self.reduced_costs[c] > epsilon
+                    }
+            });
+            let entering = if iterations < max_dantzig_iterations {
+                candidates.max_by(|&a, &b| {
+                    self.reduced_costs[a]
+                        .abs()
+                        .total_cmp(&self.reduced_costs[b].abs())
+                })
+            } else {
+                candidates.next()
+            };
+            let Some(entering) = entering else {
+                break;
+            };
+            iterations += 1;
+
+            // The entering variable increases from the lower bound or decreases from the upper bound.
+            let direction = if self.at_upper[entering] { -1.0 } else { 1.0 };
+            let mut step = self.upper[entering] - self.lower[entering];
+            let mut leaving = None;
+
+            for (r, (row, &value)) in self.rows.iter().zip(self.values.iter()).enumerate() {
+                let alpha = direction * row[entering];
+                let limit = if alpha > epsilon {
+                    (value - self.lower[self.basis[r]]) / alpha
+                } else if alpha < -epsilon {
+                    (self.upper[self.basis[r]] - value) / -alpha
+                } else {
+                    continue;
+                };
+
+                if limit < step {
+                    step = limit;
+                    leaving = Some(r);
+                }
+            }
+
+            if step.is_infinite() {
+                break;
+            }
+
+            for (row, value) in self.rows.iter().zip(self.values.iter_mut()) {
+                *value -= direction * step * row[entering];
+            }
+
+            let Some(r) = leaving else {
+                self.at_upper[entering] = !self.at_upper[entering];
+
+                continue;
+            };
+
+            let entering_value = self.get_nonbasic_value(entering) + direction * step;
+            self.at_upper[self.basis[r]] = direction * self.rows[r][entering] < 0.0;
+            self.pivot(r, entering, entering_value);
+        }
+    }
+
+    // The leaving variable is the basic variable with the largest violation of its bounds,
+    // and the entering variable is selected by the ratio test to keep the reduced costs dual feasible.
+    // Returns false if the LP is infeasible.
+    fn run_dual_simplex(&mut self, epsilon: f64) -> bool {
+        let n_columns = self.reduced_costs.len();
+        let max_iterations = 50 * n_columns;
+
+        for _ in 0..max_iterations {
+            let violation = |r: usize| {
+                let c = self.basis[r];
+
+                (self.lower[c] - self.values[r]).max(self.values[r] - self.upper[c])
+            };
+            let Some(r) = (0..self.rows.len())
+                .filter(|&r| violation(r) > epsilon)
+                .max_by(|&a, &b| violation(a).total_cmp(&violation(b)))
+            else {
+                return true;
+            };
+
+            let leaving_column = self.basis[r];
+            // The leaving variable decreases to its upper bound or increases to its lower bound.
+            let to_upper = self.values[r] > self.upper[leaving_column];
+            let target = if to_upper {
+                self.upper[leaving_column]
+            } else {
+                self.lower[leaving_column]
+            };
+            let sign = if to_upper { 1.0 } else { -1.0 };
+
+            let entering = (0..n_columns)
+                .filter(|&c| {
+                    let alpha = sign * self.rows[r][c];
+
+                    self.is_movable(c)
+                        && if self.at_upper[c] {
+                            alpha < -epsilon
+                        } else {
+                            alpha > epsilon
+                        }
+                })
+                .min_by(|&a, &b| {
+                    let ratio = |c: usize| (self.reduced_costs[c] / self.rows[r][c]).abs();
+
+                    ratio(a).total_cmp(&ratio(b))
+                });
+            let Some(entering) = entering else {
+                return false;
+            };
+
+            let step = (self.values[r] - target) / self.rows[r][entering];
+
+            for (row, value) in self.rows.iter().zip(self.values.iter_mut()) {
+                *value -= step * row[entering];
+            }
+
+            let entering_value = self.get_nonbasic_value(entering) + step;
+            self.at_upper[leaving_column] = to_upper;
+            self.pivot(r, entering, entering_value);
+        }
+
+        false
+    }
+
+    // Replaces the basic variable of row `r` with `entering` taking `value`.
+    fn pivot(&mut self, r: usize, entering: usize, value: f64) {
+        self.is_basic[self.basis[r]] = false;
+        self.is_basic[entering] = true;
+        self.at_upper[entering] = false;
+        self.basis[r] = entering;
+        self.values[r] = value;
+
+        let pivot = self.rows[r][entering];
+        self.rows[r].iter_mut().for_each(|x| *x /= pivot);
+        let pivot_row = self.rows[r].clone();
+
+        for (i, row) in self.rows.iter_mut().enumerate() {
+            let factor = row[entering];
+
+            if i != r && factor != 0.0 {
+                row.iter_mut()
+                    .zip(pivot_row.iter())
+                    .for_each(|(x, y)| *x -= factor * y);
+            }
+        }
+
+        let factor = self.reduced_costs[entering];
+        self.reduced_costs
+            .iter_mut()
+            .zip(pivot_row.iter())
+            .for_each(|(x, y)| *x -= factor * y);
+    }
+}
+
+impl Instance {
+    /// Solves the LP relaxation of the subproblem with the items from `start` and the capacities `remaining`.
+    pub fn solve_lp_relaxation(&self, start: usize, remaining: &[i32], epsilon: f64) -> LpSolution {
+        self.create_lp_tableau(start, remaining, epsilon)
+            .get_solution(start, &self.profits)
+    }
+
+    /// Solves the LP relaxation of the subproblem with the items from `start` and the capacities `remaining`
+    /// and returns the optimal tableau.
+    ///
+    /// A dense bounded-variable primal simplex method is used starting from the slack basis.
+    pub fn create_lp_tableau(&self, start: usize, remaining: &[i32], epsilon: f64) -> LpTableau {
+        let n = self.profits.len();
+        let m = remaining.len();
+        let n_columns = n + m;
+
+        let rows = self
+            .weights
+            .iter()
+            .enumerate()
+            .map(|(j, ws)| {
+                let mut row = ws.iter().map(|&w| w as f64).collect::<Vec<_>>();
+                row.resize(n_columns, 0.0);
+                row[n + j] = 1.0;
+
+                row
+            })
+            .collect::<Vec<_>>();
+        let values = remaining.iter().map(|&r| r as f64).collect();
+        let mut reduced_costs = self.profits.iter().map(|&p| p as f64).collect::<Vec<_>>();
+        reduced_costs.resize(n_columns, 0.0);
+        let mut is_basic = vec![false; n_columns];
+        is_basic[n..].iter_mut().for_each(|x| *x = true);
+        let mut upper = vec![1.0; n];
+        upper[..start].iter_mut().for_each(|x| *x = 0.0);
+        upper.resize(n_columns, f64::INFINITY);
+
+        let mut tableau = LpTableau {
+            rows,
+            values,
+            reduced_costs,
+            basis: (n..n_columns).collect(),
+            is_basic,
+            at_upper: vec![false; n_columns],
+            lower: vec![0.0; n_columns],
+            upper,
+        };
+        tableau.run_primal_simplex(epsilon);
+
+        tableau
+    }
+
+    /// Computes the upper bound on the profit of the subproblem with the items from `start` and the capacities `remaining`
+    /// by relaxing the capacity constraints with multipliers `duals` in a Lagrangian way.
+    ///
+    /// The bound is admissible for any multipliers, where negative ones are replaced with zero,
+    /// and is equal to the LP bound with the optimal dual values.
+    pub fn compute_dual_bound(&self, start: usize, remaining: &[i32], duals: &[f64]) -> f64 {
+        let duals = duals.iter().map(|&u| u.max(0.0)).collect::<Vec<_>>();
+        let capacity_term = remaining
+            .iter()
+            .zip(duals.iter())
+            .map(|(&r, &u)| u * r as f64)
+            .sum::<f64>();
+        let item_term = (start..self.profits.len())
+            .map(|i| {
+                let reduced_profit = self.profits[i] as f64
+                    - self
+                        .weights
+                        .iter()
+                        .zip(duals.iter())
+                        .map(|(ws, &u)| u * ws[i] as f64)
+                        .sum::<f64>();
+
+                reduced_profit.max(0.0)
+            })
+            .sum::<f64>();
+
+        capacity_term + item_term
+    }
+
+    /// Computes surrogate multipliers to aggregate the capacity constraints into a single constraint.
+    ///
+    /// The multipliers are the dual values of the LP relaxation of the whole problem normalized to sum to one,
+    /// so the Dantzig bound of the surrogate constraint is equal to the LP bound at the root.
+    /// If no constraint is binding, the multipliers are uniform.
+    pub fn compute_surrogate_multipliers(&self, epsilon: f64) -> Vec<f64> {
+        let m = self.capacities.len();
+        let duals = self.solve_lp_relaxation(0, &self.capacities, epsilon).duals;
+        let duals = duals.into_iter().map(|u| u.max(0.0)).collect::<Vec<_>>();
+        let sum = duals.iter().sum::<f64>();
+
+        if sum > epsilon {
+            duals.into_iter().map(|u| u / sum).collect()
+        } else {
+            vec![1.0 / m as f64; m]
+        }
+    }
+
+    /// Returns the weights of the items in the surrogate constraint.
+    pub fn get_surrogate_weights(&self, multipliers: &[f64]) -> Vec<f64> {
+        (0..self.profits.len())
+            .map(|i| {
+                self.weights
+                    .iter()
+                    .zip(multipliers)
+                    .map(|(ws, &u)| u * ws[i] as f64)
+                    .sum()
+            })
+            .collect()
+    }
+}
+
+/// Solves the instances selected by the arguments one by one.
+///
+/// `solve` takes an instance and the file to save the history.
+/// When multiple instances are solved, the index of each instance is appended to the file name,
+/// and the results are summarized at the end.
+/// The gap to the best-known profit is reported if it is given in the file.
+pub fn solve_instances(
+    args: &Args,
+    solve: impl Fn(Instance, &str) -> InstanceResult<i32>,
+) -> Result<(), Box<dyn Error>> {
+    let instances = Instance::load(&args.input_file, &args.format, args.instance)?;
+
+    io_util::solve_instances(
+        instances,
+        |instance, index| {
+            let history = io_util::add_index_to_filename(&args.history, index);
+            let result = solve(instance.clone(), &history);
+
+            if let (Some(best_known), Some(gap)) = (
+                instance.best_known,
+                result.cost.and_then(|cost| instance.compute_gap(cost)),
+            ) {
+                println!("Gap to the best-known profit {}: {}%", best_known, gap);
+            }
+
+            Some((instance, result))
+        },
+        |(instance, result)| {
+            let gap = match (instance.best_known, result.cost) {
+                (Some(best_known), Some(cost)) => format!(
+                    ", best known {}, gap {}%",
+                    best_known,
+                    instance.compute_gap(cost).unwrap()
+                ),
+                (Some(best_known), None) => format!(", best known {}", best_known),
+                _ => String::new(),
+            };
+
+            format!("{}{}", result.summarize("profit"), gap)
+        },
+    );
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum FileFormat {
+    Single,
+    Orlib,
+    Gk,
+}
+
 #[derive(Debug, Clone, ValueEnum)]
 pub enum SolverChoice {
     Cabs,
@@ -79,6 +633,13 @@ pub struct Args {
     pub history: String,
     #[arg(short, long, default_value_t = 1800.0, help = "Time limit")]
     pub time_limit: f64,
+    #[arg(long, value_enum, default_value_t = FileFormat::Single, help = "Format of the input file")]
+    pub format: FileFormat,
+    #[arg(
+        long,
+        help = "Index of the instance in a file with multiple instances starting from 1 (all instances if omitted)"
+    )]
+    pub instance: Option<usize>,
     #[arg(
         short,
         long,
@@ -87,3 +648,239 @@ pub struct Args {
     )]
     pub epsilon: f64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 1e-6;
+
+    fn create_instance(profits: &[i32], weights: &[&[i32]], capacities: &[i32]) -> Instance {
+        Instance {
+            profits: profits.to_vec(),
+            weights: weights.iter().map(|ws| ws.to_vec()).collect(),
+            capacities: capacities.to_vec(),
+            best_known: None,
+        }
+    }
+
+    // Generates a random instance with a linear congruential generator.
+    fn generate_instance(seed: u64, n: usize, m: usize) -> Instance {
+        let mut state = seed;
+        let mut next = |lb: i32, ub: i32| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+
+            lb + ((state >> 33) % (ub - lb + 1) as u64) as i32
+        };
+
+        let weights = (0..m)
+            .map(|_| (0..n).map(|_| next(1, 100)).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let profits = (0..n).map(|_| next(1, 100)).collect();
+        let capacities = weights
+            .iter()
+            .map(|ws| ws.iter().sum::<i32>() / 2)
+            .collect();
+
+        Instance {
+            profits,
+            weights,
+            capacities,
+            best_known: None,
+        }
+    }
+
+    #[test]
+    fn solve_lp_relaxation_matches_hand_computed_optimum() {
+        let instance = create_instance(&[6, 6, 4], &[&[2, 3, 4], &[3, 2, 1]], &[5, 4]);
+
+        // With item 1 packed, 2 x_0 + 4 x_2 = 2 and 3 x_0 + x_2 = 2 give x_0 = 3/5 and x_2 = 1/5.
+        // The dual values (3/5, 8/5) make the reduced profits of items 0 and 2 zero
+        // and that of item 1 positive, which certifies the optimality.
+        let solution = instance.solve_lp_relaxation(0, &instance.capacities, EPSILON);
+
+        assert!((solution.objective - 10.4).abs() < EPSILON);
+
+        for (x, expected) in solution.x.iter().zip([0.6, 1.0, 0.2]) {
+            assert!((x - expected).abs() < EPSILON);
+        }
+
+        for (u, expected) in solution.duals.iter().zip([0.6, 1.6]) {
+            assert!((u - expected).abs() < EPSILON);
+        }
+
+        // Item 0 is not in the subproblem, so 3 x_1 + 4 x_2 <= 5 and 2 x_1 + x_2 <= 4 give x = (0, 1, 1/2).
+        let solution = instance.solve_lp_relaxation(1, &instance.capacities, EPSILON);
+
+        assert!((solution.objective - 8.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn fix_item_matches_lp_solved_from_scratch() {
+        for seed in 0..32 {
+            let instance = generate_instance(seed, 10, 3);
+            let n = instance.profits.len();
+
+            // Follows the fixing order of the DP with the values of items given by the bits of the seed.
+            let mut tableau = instance.create_lp_tableau(0, &instance.capacities, EPSILON);
+            let mut remaining = instance.capacities.clone();
+
+            for i in 0..n {
+                let value = (seed >> (i % 5)) & 1 == 1;
+
+                if value {
+                    remaining
+                        .iter_mut()
+                        .zip(instance.weights.iter())
+                        .for_each(|(r, ws)| *r -= ws[i]);
+                }
+
+                let child = tableau.fix_item(i, if value { 1.0 } else { 0.0 }, EPSILON);
+
+                if remaining.iter().any(|&r| r < 0) {
+                    assert!(child.is_none());
+
+                    break;
+                }
+
+                tableau = child.unwrap();
+                let warm = tableau.get_solution(i + 1, &instance.profits);
+                let cold = instance.solve_lp_relaxation(i + 1, &remaining, EPSILON);
+
+                assert!((warm.objective - cold.objective).abs() < 1e-6 * cold.objective.max(1.0));
+            }
+        }
+    }
+
+    #[test]
+    fn compute_dual_bound_is_at_least_lp_objective() {
+        for seed in 0..20 {
+            let instance = generate_instance(seed, 10, 3);
+            let m = instance.capacities.len();
+
+            for start in 0..instance.profits.len() {
+                let remaining = instance
+                    .capacities
+                    .iter()
+                    .map(|&c| c * (start as i32 + 1) / 10)
+                    .collect::<Vec<_>>();
+                let solution = instance.solve_lp_relaxation(start, &remaining, EPSILON);
+
+                // The bound with the optimal dual values is the LP bound.
+                let bound = instance.compute_dual_bound(start, &remaining, &solution.duals);
+                assert!(bound >= solution.objective - EPSILON);
+                assert!(bound <= solution.objective + 1e-6 * solution.objective.max(1.0));
+
+                // Any multipliers give a weaker bound, where negative ones are replaced with zero.
+                for k in 0..m {
+                    let mut duals = solution.duals.clone();
+                    duals[k] += 0.5;
+                    let bound = instance.compute_dual_bound(start, &remaining, &duals);
+                    assert!(bound >= solution.objective - EPSILON);
+
+                    duals[k] = -1.0;
+                    let bound = instance.compute_dual_bound(start, &remaining, &duals);
+                    assert!(bound >= solution.objective - EPSILON);
+                }
+            }
+        }
+    }
+
+    // Writes `content` to a file in the temporary directory and returns its path.
+    fn write_file(name: &str, content: &str) -> String {
+        let filename = std::env::temp_dir().join(name);
+        fs::write(&filename, content).unwrap();
+
+        filename.to_str().unwrap().to_owned()
+    }
+
+    const ORLIB_FILE: &str = "2\n\
+        3 2 30\n\
+        10 20 30\n\
+        1 2 3\n\
+        4 5 6\n\
+        5 9\n\
+        2 1 0\n\
+        7 8\n\
+        3 4\n\
+        6\n";
+
+    #[test]
+    fn read_all_from_orlib_file_reads_each_instance() {
+        let filename = write_file("mdkp_read_all_from_orlib_file.txt", ORLIB_FILE);
+        let instances = Instance::read_all_from_orlib_file(&filename).unwrap();
+
+        assert_eq!(instances.len(), 2);
+        assert_eq!(instances[0].profits, vec![10, 20, 30]);
+        assert_eq!(instances[0].weights, vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        assert_eq!(instances[0].capacities, vec![5, 9]);
+        assert_eq!(instances[0].best_known, Some(30));
+        assert_eq!(instances[1].profits, vec![7, 8]);
+        assert_eq!(instances[1].weights, vec![vec![3, 4]]);
+        assert_eq!(instances[1].capacities, vec![6]);
+        assert_eq!(instances[1].best_known, None);
+    }
+
+    #[test]
+    fn read_all_from_orlib_file_rejects_truncated_file() {
+        let filename = write_file(
+            "mdkp_read_all_from_orlib_file_truncated.txt",
+            "2\n3 2 30\n10 20 30\n1 2 3\n4 5 6\n5 9\n",
+        );
+
+        assert!(Instance::read_all_from_orlib_file(&filename).is_err());
+    }
+
+    #[test]
+    fn read_from_gk_file_reads_constraints_with_capacities() {
+        let filename = write_file(
+            "mdkp_read_from_gk_file.txt",
+            "2 3 25.6\n\
+             10 20 30\n\
+             1 2 3 5\n\
+             4 5 6 9\n",
+        );
+        let instance = Instance::read_from_gk_file(&filename).unwrap();
+
+        assert_eq!(instance.profits, vec![10, 20, 30]);
+        assert_eq!(instance.weights, vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        assert_eq!(instance.capacities, vec![5, 9]);
+        assert_eq!(instance.best_known, Some(26));
+    }
+
+    #[test]
+    fn load_selects_instance_by_index() {
+        let filename = write_file("mdkp_load_selects_instance_by_index.txt", ORLIB_FILE);
+
+        let instances = Instance::load(&filename, &FileFormat::Orlib, None).unwrap();
+        assert_eq!(
+            instances.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+
+        let instances = Instance::load(&filename, &FileFormat::Orlib, Some(2)).unwrap();
+        assert_eq!(instances.len(), 1);
+        assert_eq!(instances[0].0, 2);
+        assert_eq!(instances[0].1.profits, vec![7, 8]);
+
+        assert!(Instance::load(&filename, &FileFormat::Orlib, Some(0)).is_err());
+        assert!(Instance::load(&filename, &FileFormat::Orlib, Some(3)).is_err());
+    }
+
+    #[test]
+    fn load_treats_single_instance_as_index_one() {
+        let filename = write_file(
+            "mdkp_load_treats_single_instance_as_index_one.txt",
+            "3 2 30\n10 20 30\n1 2 3\n4 5 6\n5 9\n",
+        );
+
+        let instances = Instance::load(&filename, &FileFormat::Single, Some(1)).unwrap();
+        assert_eq!(instances.len(), 1);
+        assert_eq!(instances[0].0, 1);
+
+        assert!(Instance::load(&filename, &FileFormat::Single, Some(0)).is_err());
+        assert!(Instance::load(&filename, &FileFormat::Single, Some(2)).is_err());
+    }
+}
@@ -3,8 +3,10 @@ use fixedbitset::FixedBitSet;
 use optw::{Args, Instance, RoundedInstance, SolverChoice};
 use rpid::prelude::*;
 use rpid::{algorithms, io, solvers, timer::Timer};
+use std::cell::Cell;
 use std::cmp;
 use std::cmp::Ordering;
+use std::rc::Rc;
 
 #[cfg(not(target_env = "msvc"))]
 use tikv_jemallocator::Jemalloc;
@@ -18,6 +20,7 @@ struct Optw {
     shortest_distances: Vec<Vec<i32>>,
     min_distance_from: Vec<i32>,
     min_distance_to: Vec<i32>,
+    n_vehicles: i32,
     sorted_weight_value_pairs_from: Vec<(usize, i32, i32)>,
     sorted_weight_value_pairs_to: Vec<(usize, i32, i32)>,
     epsilon: f64,
@@ -41,6 +44,7 @@ impl Optw {
             algorithms::sort_knapsack_items_by_efficiency(&min_distance_to, &instance.profits);
 
         Self {
+            n_vehicles: instance.n_vehicles as i32,
             instance,
             shortest_distances,
             min_distance_from,
@@ -56,6 +60,39 @@ struct OptwState {
     unvisited: FixedBitSet,
     current: usize,
     time: i32,
+    n_vehicles: i32,
+}
+
+impl Optw {
+    // Returns whether customer `i` can be visited in the current route or in a new route.
+    fn is_reachable(&self, state: &OptwState, i: usize) -> bool {
+        let shortest_distances = &self.shortest_distances;
+
+        self.instance
+            .is_reachable(state.current, state.time, i, shortest_distances)
+            || (state.n_vehicles < self.n_vehicles
+                && self.instance.is_reachable(0, 0, i, shortest_distances))
+    }
+
+    fn visit(&self, state: &OptwState, next: usize, new_route: bool) -> Option<OptwState> {
+        let (current, time, n_vehicles) = if new_route {
+            (0, 0, state.n_vehicles + 1)
+        } else {
+            (state.current, state.time, state.n_vehicles)
+        };
+        let time = self
+            .instance
+            .get_visit_time(current, time, next, &self.shortest_distances)?;
+        let mut unvisited = state.unvisited.clone();
+        unvisited.remove(next);
+
+        Some(OptwState {
+            unvisited,
+            current: next,
+            time,
+            n_vehicles,
+        })
+    }
 }
 
 impl Dp for Optw {
@@ -70,6 +107,7 @@ impl Dp for Optw {
             unvisited,
             current: 0,
             time: 0,
+            n_vehicles: 1,
         }
     }
 
@@ -80,68 +118,78 @@ impl Dp for Optw {
         let n = self.instance.vertices.len();
 
         for next in state.unvisited.ones() {
-            let shortest_time = state.time + self.shortest_distances[state.current][next];
+            if !self.is_reachable(state, next) {
+                // A mandatory customer that cannot be visited makes the state infeasible.
+                if self.instance.mandatory[next] {
+                    return vec![];
+                }
 
-            if shortest_time > self.instance.closing[next]
-                || shortest_time + self.shortest_distances[next][0] > self.instance.closing[0]
-            {
                 let mut unvisited = state.unvisited.clone();
                 unvisited.remove(next);
                 let state = OptwState {
                     unvisited,
                     current: state.current,
                     time: state.time,
+                    n_vehicles: state.n_vehicles,
                 };
 
                 return vec![(state, 0, n + next)];
             }
         }
 
-        if let Some(next) = state.unvisited.ones().next() {
+        // Customers that cannot be visited next are visited by another vehicle if any.
+        if let Some(next) = state
+            .unvisited
+            .ones()
+            .next()
+            .filter(|_| state.n_vehicles >= self.n_vehicles)
+        {
             if state.unvisited.ones().all(|via| {
                 let via_time = state.time + self.instance.distances[state.current][via];
 
                 (via_time > self.instance.closing[via])
                     || (via_time + self.shortest_distances[via][0] > self.instance.closing[0])
             }) {
+                if state.unvisited.ones().any(|i| self.instance.mandatory[i]) {
+                    return vec![];
+                }
+
                 let mut unvisited = state.unvisited.clone();
                 unvisited.remove(next);
                 let state = OptwState {
                     unvisited,
                     current: state.current,
                     time: state.time,
+                    n_vehicles: state.n_vehicles,
                 };
 
                 return vec![(state, 0, 2 * n + next)];
             }
         }
 
-        state
+        let mut successors = state
             .unvisited
             .ones()
             .filter_map(|next| {
-                let time = cmp::max(
-                    state.time + self.instance.distances[state.current][next],
-                    self.instance.opening[next],
-                );
-
-                if time <= self.instance.closing[next]
-                    && time + self.shortest_distances[next][0] <= self.instance.closing[0]
-                {
-                    let mut unvisited = state.unvisited.clone();
-                    unvisited.remove(next);
-                    let state = OptwState {
-                        unvisited,
-                        current: next,
-                        time,
-                    };
-
-                    Some((state, self.instance.profits[next], next))
-                } else {
-                    None
-                }
+                let successor = self.visit(state, next, false)?;
+
+                Some((successor, self.instance.profits[next], next))
             })
-            .collect()
+            .collect::<Vec<_>>();
+
+        // The route is empty only in the target state.
+        if state.current != 0
+            && state.n_vehicles < self.n_vehicles
+            && state.time + self.instance.distances[state.current][0] <= self.instance.closing[0]
+        {
+            successors.extend(state.unvisited.ones().filter_map(|next| {
+                let successor = self.visit(state, next, true)?;
+
+                Some((successor, self.instance.profits[next], 3 * n + next))
+            }));
+        }
+
+        successors
     }
 
     fn get_base_cost(&self, state: &Self::State) -> Option<Self::CostType> {
@@ -168,7 +216,15 @@ impl Dominance for Optw {
     }
 
     fn compare(&self, a: &Self::State, b: &Self::State) -> Option<Ordering> {
-        Some(b.time.cmp(&a.time))
+        if a.time == b.time && a.n_vehicles == b.n_vehicles {
+            Some(Ordering::Equal)
+        } else if a.time <= b.time && a.n_vehicles <= b.n_vehicles {
+            Some(Ordering::Greater)
+        } else if a.time >= b.time && a.n_vehicles >= b.n_vehicles {
+            Some(Ordering::Less)
+        } else {
+            None
+        }
     }
 }
 
@@ -177,23 +233,29 @@ impl Bound for Optw {
     type CostType = i32;
 
     fn get_dual_bound(&self, state: &Self::State) -> Option<Self::CostType> {
-        let candidates = state
+        if state
             .unvisited
             .ones()
-            .filter(|&i| {
-                let shortest_time = state.time + self.shortest_distances[state.current][i];
+            .any(|i| self.instance.mandatory[i] && !self.is_reachable(state, i))
+        {
+            return None;
+        }
 
-                shortest_time <= self.instance.closing[i]
-                    && shortest_time + self.shortest_distances[i][0] <= self.instance.closing[0]
-            })
+        let candidates = state
+            .unvisited
+            .ones()
+            .filter(|&i| self.is_reachable(state, i))
             .collect::<FixedBitSet>();
 
         if candidates.is_empty() {
             return Some(0);
         }
 
+        // Each remaining vehicle can travel from the depot until its closing time.
+        let remaining_vehicles = self.n_vehicles - state.n_vehicles;
         let capacity_from =
-            self.instance.closing[0] - state.time - self.min_distance_from[state.current];
+            self.instance.closing[0] - state.time - self.min_distance_from[state.current]
+                + remaining_vehicles * (self.instance.closing[0] - self.min_distance_from[0]);
         let sorted_weight_value_pairs_from =
             self.sorted_weight_value_pairs_from
                 .iter()
@@ -210,7 +272,8 @@ impl Bound for Optw {
             self.epsilon,
         ) as i32;
 
-        let capacity_to = self.instance.closing[0] - state.time - self.min_distance_to[0];
+        let capacity_to = self.instance.closing[0] - state.time - self.min_distance_to[0]
+            + remaining_vehicles * (self.instance.closing[0] - self.min_distance_to[0]);
         let sorted_weight_value_pairs_to =
             self.sorted_weight_value_pairs_to
                 .iter()
@@ -235,8 +298,11 @@ fn main() {
     let timer = Timer::default();
     let args = Args::parse();
 
-    let instance = Instance::read_from_file(&args.input_file).unwrap();
-    let rounded_instance = RoundedInstance::new(instance, args.round_to);
+    let instance = Instance::load(&args.input_file, &args.mandatory).unwrap();
+    let mut rounded_instance = RoundedInstance::new(instance, args.round_to, args.profit_round_to);
+    rounded_instance.n_vehicles = args.vehicles;
+    println!("Number of vehicles: {}", rounded_instance.n_vehicles);
+
     let optw = Optw::new(rounded_instance.clone(), args.epsilon);
 
     let parameters = SearchParameters {
@@ -245,6 +311,32 @@ fn main() {
     };
 
     let solution = match args.solver {
+        SolverChoice::Cabs if args.local_search => {
+            let cabs_parameters = CabsParameters::default();
+            let primal_bound = Rc::new(Cell::new(None));
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let mut solver = io_util::create_cabs_with_shared_primal_bound(
+                optw,
+                parameters,
+                cabs_parameters,
+                primal_bound.clone(),
+            );
+            io_util::run_solver_with_local_search_and_dump_solution_history(
+                &mut solver,
+                &args.history,
+                OptimizationMode::Maximization,
+                &primal_bound,
+                |transitions| {
+                    let routes = rounded_instance.transitions_to_routes(transitions);
+                    rounded_instance
+                        .improve_routes(&routes)
+                        .map(|(profit, routes)| {
+                            (profit, rounded_instance.routes_to_transitions(&routes))
+                        })
+                },
+            )
+            .unwrap()
+        }
         SolverChoice::Cabs => {
             let cabs_parameters = CabsParameters::default();
             println!("Preparing time: {}s", timer.get_elapsed_time());
@@ -260,14 +352,14 @@ fn main() {
     io::print_solution_statistics(&solution);
 
     if let Some(profit) = solution.cost {
-        let tour = solution
-            .transitions
-            .into_iter()
-            .filter(|&i| i < rounded_instance.vertices.len())
-            .collect::<Vec<_>>();
-        rounded_instance.print_solution(&tour);
-
-        if rounded_instance.validate(&tour, profit) {
+        println!(
+            "Profit in the original units: {}",
+            rounded_instance.get_original_profit(profit)
+        );
+        let routes = rounded_instance.transitions_to_routes(&solution.transitions);
+        rounded_instance.print_solution(&routes);
+
+        if rounded_instance.validate(&routes, profit) {
             println!("The solution is valid.");
         } else {
             println!("The solution is invalid.");
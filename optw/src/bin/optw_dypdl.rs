@@ -19,8 +19,14 @@ fn main() {
     let timer = Timer::default();
     let args = Args::parse();
 
-    let instance = Instance::read_from_file(&args.input_file).unwrap();
-    let rounded_instance = RoundedInstance::new(instance, args.round_to);
+    if args.vehicles > 1 {
+        println!("Use optw_rpid or optw_dantzig_rpid for multiple vehicles");
+
+        return;
+    }
+
+    let instance = Instance::load(&args.input_file, &args.mandatory).unwrap();
+    let rounded_instance = RoundedInstance::new(instance, args.round_to, args.profit_round_to);
 
     let mut model = Model::default();
     model.set_maximize();
@@ -75,7 +81,8 @@ fn main() {
         )
         .unwrap();
 
-    for next in 1..n {
+    // Mandatory customers are never removed.
+    for next in (1..n).filter(|&i| !rounded_instance.mandatory[i]) {
         let mut remove = Transition::new(format!("{}", n + next));
         remove.set_cost(IntegerExpression::Cost);
         remove
@@ -100,7 +107,7 @@ fn main() {
         model.add_forward_forced_transition(remove).unwrap()
     }
 
-    for next in 1..n {
+    for next in (1..n).filter(|&i| !rounded_instance.mandatory[i]) {
         let mut clear = Transition::new(format!("{}", 2 * n + next));
         clear.set_cost(IntegerExpression::Cost);
         clear.add_effect(unvisited, unvisited.remove(next)).unwrap();
@@ -134,8 +141,19 @@ fn main() {
         visit.add_effect(unvisited, unvisited.remove(next)).unwrap();
         visit.add_effect(current, next).unwrap();
         let arrival_time = time + distances.element(current, next);
-        let start_time =
-            IntegerExpression::max(arrival_time.clone(), rounded_instance.opening[next]);
+        // The service starts in the first time window not closed yet.
+        let windows = &rounded_instance.time_windows[next];
+        let (last_opening, _) = windows[windows.len() - 1];
+        let start_time = windows.iter().rev().skip(1).fold(
+            IntegerExpression::max(arrival_time.clone(), last_opening),
+            |start_time, &(opening, closing)| {
+                Condition::comparison_i(ComparisonOperator::Le, arrival_time.clone(), closing)
+                    .if_then_else(
+                        IntegerExpression::max(arrival_time.clone(), opening),
+                        start_time,
+                    )
+            },
+        );
         visit.add_effect(time, start_time).unwrap();
 
         visit.add_precondition(unvisited.contains(next));
@@ -163,6 +181,25 @@ fn main() {
         .add_base_case(vec![unvisited.is_empty(), on_time])
         .unwrap();
 
+    for i in (1..n).filter(|&i| rounded_instance.mandatory[i]) {
+        // A mandatory customer must be reachable until it is visited.
+        let shortest_time = time + shortest_distances.element(current, i);
+        let on_time = Condition::comparison_i(
+            ComparisonOperator::Le,
+            shortest_time,
+            rounded_instance.closing[i],
+        );
+        let shortest_return_time = time + shortest_return_distances.element(current, i);
+        let on_time_return = Condition::comparison_i(
+            ComparisonOperator::Le,
+            shortest_return_time,
+            rounded_instance.closing[0],
+        );
+        model
+            .add_state_constraint(!unvisited.contains(i) | (on_time & on_time_return))
+            .unwrap();
+    }
+
     let mut max_profit_bound: Option<IntegerExpression> = None;
 
     for (i, &p) in rounded_instance.profits.iter().enumerate().skip(1) {
@@ -299,22 +336,19 @@ fn main() {
     io_util::print_solution_statistics(&solution);
 
     if let Some(profit) = solution.cost {
-        let tour = solution
+        println!(
+            "Profit in the original units: {}",
+            rounded_instance.get_original_profit(profit)
+        );
+        let transitions = solution
             .transitions
             .iter()
-            .filter_map(|t| {
-                let i = t.get_full_name().parse().unwrap();
-
-                if i < n {
-                    Some(i)
-                } else {
-                    None
-                }
-            })
+            .map(|t| t.get_full_name().parse().unwrap())
             .collect::<Vec<_>>();
-        rounded_instance.print_solution(&tour);
+        let routes = rounded_instance.transitions_to_routes(&transitions);
+        rounded_instance.print_solution(&routes);
 
-        if rounded_instance.validate(&tour, profit) {
+        if rounded_instance.validate(&routes, profit) {
             println!("The solution is valid.");
         } else {
             println!("The solution is invalid.");
@@ -12,11 +12,31 @@ pub struct Instance {
     pub coordinates: Vec<(f64, f64)>,
     pub service_time: Vec<f64>,
     pub profits: Vec<f64>,
-    pub opening: Vec<f64>,
-    pub closing: Vec<f64>,
+    pub time_windows: Vec<Vec<(f64, f64)>>,
+    pub mandatory: Vec<bool>,
 }
 
 impl Instance {
+    /// Reads an instance from a file and marks customers given by their ids as mandatory.
+    pub fn load(filename: &str, mandatory: &[usize]) -> Result<Self, Box<dyn Error>> {
+        let mut instance = Self::read_from_file(filename)?;
+
+        for &id in mandatory {
+            match instance.vertices.iter().position(|&v| v == id) {
+                Some(i) if i > 0 => instance.mandatory[i] = true,
+                _ => return Err(format!("{} is not a customer", id).into()),
+            }
+        }
+
+        Ok(instance)
+    }
+
+    /// Reads an instance from a file.
+    ///
+    /// Each line of a vertex gives the id, the coordinates, the service time, the profit,
+    /// the frequency, the number of visit combinations, the combinations, and the time windows.
+    /// Multiple time windows are given as a sequence of opening and closing times.
+    /// The format has no field for mandatory customers, so they are given to `load` instead.
     pub fn read_from_file(filename: &str) -> Result<Self, Box<dyn Error>> {
         let file = File::open(filename)?;
         let mut lines = BufReader::new(file).lines();
@@ -37,8 +57,7 @@ impl Instance {
         let mut points = Vec::with_capacity(n);
         let mut service_time = Vec::with_capacity(n);
         let mut profits = Vec::with_capacity(n);
-        let mut opening = Vec::with_capacity(n);
-        let mut closing = Vec::with_capacity(n);
+        let mut time_windows = Vec::with_capacity(n);
 
         for i in 0..n {
             let line = lines
@@ -59,22 +78,13 @@ impl Instance {
                 .ok_or("failed to parse the service time")?
                 .parse()?;
             let p = digits.next().ok_or("failed to parse the profit")?.parse()?;
-            let mut digits = digits.rev();
-            let b = digits
-                .next()
-                .ok_or("failed to parse the closing time")?
-                .parse()?;
-            let a = digits
-                .next()
-                .ok_or("failed to parse the opening time")?
-                .parse()?;
+            let digits = digits.collect::<Vec<_>>();
 
             vertices.push(v);
             points.push((x, y));
             service_time.push(s);
             profits.push(p);
-            opening.push(a);
-            closing.push(b);
+            time_windows.push(Self::parse_time_windows(&digits)?);
         }
 
         Ok(Self {
@@ -82,82 +92,271 @@ impl Instance {
             coordinates: points,
             service_time,
             profits,
-            opening,
-            closing,
+            time_windows,
+            mandatory: vec![false; n],
         })
     }
+
+    fn parse_time_windows(digits: &[&str]) -> Result<Vec<(f64, f64)>, Box<dyn Error>> {
+        let n_combinations = digits
+            .get(1)
+            .ok_or("failed to parse the number of visit combinations")?
+            .parse::<usize>()?;
+        let windows = digits.get(n_combinations + 2..).unwrap_or_default();
+
+        if windows.is_empty() || !windows.len().is_multiple_of(2) {
+            return Err(format!(
+                "failed to parse the time windows from {} values",
+                windows.len()
+            )
+            .into());
+        }
+
+        windows
+            .chunks(2)
+            .map(|window| Ok((window[0].parse()?, window[1].parse()?)))
+            .collect()
+    }
 }
 
+/// Orienteering instance with distances and times multiplied by a power of ten and truncated,
+/// and profits multiplied by another power of ten and rounded.
+///
+/// Each of the `n_vehicles` vehicles starts from the depot at time 0 and returns to it
+/// by its closing time, and each customer is visited at most once by any vehicle.
+/// The service at a customer starts in one of its time windows, sorted by the opening times,
+/// and `opening` and `closing` are the earliest opening and the latest closing times.
+/// Mandatory customers must be visited.
 #[derive(Clone, Debug)]
 pub struct RoundedInstance {
+    pub n_vehicles: usize,
+    pub profit_round_to: u32,
     pub vertices: Vec<usize>,
     pub distances: Vec<Vec<i32>>,
+    pub service_times: Vec<i32>,
     pub profits: Vec<i32>,
+    pub time_windows: Vec<Vec<(i32, i32)>>,
     pub opening: Vec<i32>,
     pub closing: Vec<i32>,
+    pub mandatory: Vec<bool>,
 }
 
 impl RoundedInstance {
-    pub fn new(instance: Instance, round_to: u32) -> Self {
+    pub fn new(instance: Instance, round_to: u32, profit_round_to: u32) -> Self {
         let pow = 10f64.powf(round_to as f64);
+        let profit_pow = 10f64.powf(profit_round_to as f64);
 
         let distances = algorithms::compute_pairwise_euclidean_distances(&instance.coordinates);
         let distances = distances
             .into_iter()
-            .zip(instance.service_time)
-            .map(|(row, s)| {
+            .zip(instance.service_time.iter())
+            .map(|(row, &s)| {
                 row.into_iter()
                     .map(|d| ((s + d) * pow).trunc() as i32)
                     .collect()
             })
             .collect();
-        let profits = instance.profits.into_iter().map(|p| p as i32).collect();
-        let opening = instance
-            .opening
+        let profits = instance
+            .profits
+            .into_iter()
+            .map(|p| (p * profit_pow).round() as i32)
+            .collect();
+        let service_times = instance
+            .service_time
             .into_iter()
-            .map(|t| (t * pow).trunc() as i32)
+            .map(|s| (s * pow).trunc() as i32)
             .collect();
-        let closing = instance
-            .closing
+        let time_windows = instance
+            .time_windows
             .into_iter()
-            .map(|t| (t * pow).trunc() as i32)
+            .map(|windows| {
+                let mut windows = windows
+                    .into_iter()
+                    .map(|(a, b)| ((a * pow).trunc() as i32, (b * pow).trunc() as i32))
+                    .collect::<Vec<_>>();
+                windows.sort();
+
+                windows
+            })
+            .collect::<Vec<Vec<_>>>();
+        let opening = time_windows.iter().map(|windows| windows[0].0).collect();
+        let closing = time_windows
+            .iter()
+            .map(|windows| windows.iter().map(|&(_, b)| b).max().unwrap())
             .collect();
 
         Self {
+            n_vehicles: 1,
+            profit_round_to,
             vertices: instance.vertices,
             distances,
+            service_times,
             profits,
+            time_windows,
             opening,
             closing,
+            mandatory: instance.mandatory,
+        }
+    }
+
+    /// Returns the earliest time to start the service at `v` when arriving at time `arrival`,
+    /// or `None` if all the time windows are closed.
+    pub fn get_start_time(&self, v: usize, arrival: i32) -> Option<i32> {
+        self.time_windows[v]
+            .iter()
+            .find(|&&(_, b)| arrival <= b)
+            .map(|&(a, _)| cmp::max(arrival, a))
+    }
+
+    /// Returns the time to start the service at `next` when leaving `current` at `time`,
+    /// or `None` if all the time windows are closed
+    /// or the vehicle cannot return to the depot by its closing time after the service.
+    ///
+    /// `shortest_distances` are the shortest travel times between vertices.
+    pub fn get_visit_time(
+        &self,
+        current: usize,
+        time: i32,
+        next: usize,
+        shortest_distances: &[Vec<i32>],
+    ) -> Option<i32> {
+        let start = self.get_start_time(next, time + self.distances[current][next])?;
+
+        if start + shortest_distances[next][0] <= self.closing[0] {
+            Some(start)
+        } else {
+            None
+        }
+    }
+
+    /// Checks if `next` may still be visited after leaving `current` at `time`,
+    /// assuming that the vehicle travels along the shortest paths.
+    pub fn is_reachable(
+        &self,
+        current: usize,
+        time: i32,
+        next: usize,
+        shortest_distances: &[Vec<i32>],
+    ) -> bool {
+        self.get_start_time(next, time + shortest_distances[current][next])
+            .is_some_and(|start| start + shortest_distances[next][0] <= self.closing[0])
+    }
+
+    /// Converts a profit of the rounded instance back to the original units.
+    pub fn get_original_profit(&self, profit: i32) -> f64 {
+        profit as f64 / 10f64.powf(self.profit_round_to as f64)
+    }
+
+    pub fn validate(&self, routes: &[Vec<usize>], cost: i32) -> bool {
+        if routes.len() > self.n_vehicles {
+            println!(
+                "Invalid number of vehicles {} > {}",
+                routes.len(),
+                self.n_vehicles
+            );
+
+            return false;
+        }
+
+        let n = self.vertices.len();
+        let mut visited_by = vec![None; n];
+
+        for (k, route) in routes.iter().enumerate() {
+            for &v in route {
+                if v == 0 {
+                    println!("the depot is visited in the middle of route {}", k);
+
+                    return false;
+                }
+
+                if v >= n {
+                    println!("customer {} is not in the instance", v);
+
+                    return false;
+                }
+
+                if let Some(l) = visited_by[v] {
+                    println!("customer {} is visited twice by routes {} and {}", v, l, k);
+
+                    return false;
+                }
+
+                visited_by[v] = Some(k);
+            }
+        }
+
+        if let Some(v) = (1..n).find(|&v| self.mandatory[v] && visited_by[v].is_none()) {
+            println!("mandatory customer {} is not visited", v);
+
+            return false;
         }
+
+        let mut recomputed_profit = 0;
+
+        for (k, route) in routes.iter().enumerate() {
+            if self.n_vehicles > 1 {
+                println!("Tour {}:", k + 1);
+            }
+
+            let Some((profit, _)) = self.evaluate_tour(route, true) else {
+                return false;
+            };
+
+            recomputed_profit += profit;
+        }
+
+        if recomputed_profit != cost {
+            println!("Invalid profit {} != {}", recomputed_profit, cost);
+
+            return false;
+        }
+
+        true
     }
 
-    pub fn validate(&self, solution: &[usize], cost: i32) -> bool {
+    /// Returns the profit and the return time of a tour, or `None` if the tour is infeasible.
+    ///
+    /// If `verbose` is true, the arrival, start, and departure times at each stop are printed.
+    fn evaluate_tour(&self, solution: &[usize], verbose: bool) -> Option<(i32, i32)> {
         let n = self.vertices.len();
-        let mut visited = vec![false; n];
         let mut current = 0;
         let mut time = 0;
         let mut recomputed_profit = 0;
 
         for &v in solution {
-            if v > n {
-                println!("customer {} is not in the instance", v);
+            if v == 0 || v >= n {
+                if verbose {
+                    println!("customer {} is not in the instance", v);
+                }
 
-                return false;
+                return None;
             }
 
-            time = cmp::max(time + self.distances[current][v], self.opening[v]);
+            let arrival = time + self.distances[current][v];
+
+            let Some(start) = self.get_start_time(v, arrival) else {
+                if verbose {
+                    println!(
+                        "customer {} is visited at time {} after closing time {}",
+                        v, arrival, self.closing[v]
+                    );
+                }
+
+                return None;
+            };
 
-            if time > self.closing[v] {
+            if verbose {
                 println!(
-                    "customer {} is visited at time {} after closing time {}",
-                    v, time, self.closing[v]
+                    "customer {}: arrival {}, start {}, departure {}",
+                    v,
+                    arrival,
+                    start,
+                    start + self.service_times[v]
                 );
-
-                return false;
             }
 
-            visited[v] = true;
+            time = start;
+
             recomputed_profit += self.profits[v];
             current = v;
         }
@@ -165,32 +364,148 @@ impl RoundedInstance {
         time += self.distances[current][0];
 
         if time > self.closing[0] {
-            println!(
-                "the vehicle returns to the depot at time {} after closing time {}",
-                time, self.closing[0]
-            );
+            if verbose {
+                println!(
+                    "the vehicle returns to the depot at time {} after closing time {}",
+                    time, self.closing[0]
+                );
+            }
 
-            return false;
+            return None;
         }
 
-        if recomputed_profit != cost {
-            println!("Invalid profit {} != {}", recomputed_profit, cost);
+        Some((recomputed_profit, time))
+    }
 
-            return false;
+    /// Improves routes by inserting unvisited customers into each route in turn.
+    ///
+    /// Returns the improved profit and routes if an improvement is found.
+    pub fn improve_routes(&self, routes: &[Vec<usize>]) -> Option<(i32, Vec<Vec<usize>>)> {
+        let mut routes = routes.to_vec();
+        let mut is_improved = false;
+
+        for k in 0..routes.len() {
+            let mut visited = vec![false; self.vertices.len()];
+
+            for &i in routes.iter().flatten() {
+                visited[i] = true;
+            }
+
+            if let Some((_, route)) = self.improve_tour(&routes[k], &visited) {
+                routes[k] = route;
+                is_improved = true;
+            }
         }
 
-        true
+        if is_improved {
+            let profit = routes
+                .iter()
+                .flatten()
+                .map(|&i| self.profits[i])
+                .sum::<i32>();
+
+            Some((profit, routes))
+        } else {
+            None
+        }
+    }
+
+    /// Improves a tour by inserting customers not visited by any route,
+    /// making room for them by 2-opt and or-opt moves that reduce the return time.
+    ///
+    /// Returns the improved profit and tour if an improvement is found.
+    fn improve_tour(&self, tour: &[usize], visited: &[bool]) -> Option<(i32, Vec<usize>)> {
+        let return_time = |tour: &[usize]| self.evaluate_tour(tour, false).map(|(_, time)| time);
+        let shorten = |tour: Vec<usize>| {
+            io_util::improve_sequence_by_local_search(&tour, return_time)
+                .map_or(tour, |(_, tour)| tour)
+        };
+
+        let (initial_profit, _) = self.evaluate_tour(tour, false)?;
+        let mut profit = initial_profit;
+        let mut tour = shorten(tour.to_vec());
+        let mut unvisited = (1..self.vertices.len())
+            .filter(|&i| !visited[i] && self.profits[i] > 0)
+            .collect::<Vec<_>>();
+        unvisited.sort_by_key(|&i| cmp::Reverse(self.profits[i]));
+
+        while let Some((k, candidate)) = unvisited.iter().enumerate().find_map(|(k, &i)| {
+            (0..=tour.len())
+                .filter_map(|j| {
+                    let mut candidate = tour.clone();
+                    candidate.insert(j, i);
+
+                    return_time(&candidate).map(|time| (time, candidate))
+                })
+                .min_by_key(|(time, _)| *time)
+                .map(|(_, candidate)| (k, candidate))
+        }) {
+            profit += self.profits[unvisited.remove(k)];
+            tour = shorten(candidate);
+        }
+
+        if profit > initial_profit {
+            Some((profit, tour))
+        } else {
+            None
+        }
     }
 
-    pub fn print_solution(&self, solution: &[usize]) {
-        println!(
-            "Tour: {}",
-            solution
+    /// Splits transitions of the RPID models into routes.
+    ///
+    /// A transition `i < n` visits customer `i` in the current route,
+    /// a transition `i >= 3n` visits customer `i - 3n` in a new route,
+    /// and the other transitions remove customers.
+    pub fn transitions_to_routes(&self, transitions: &[usize]) -> Vec<Vec<usize>> {
+        let n = self.vertices.len();
+        let mut routes = vec![vec![]];
+
+        for &transition in transitions {
+            if transition < n {
+                routes.last_mut().unwrap().push(transition);
+            } else if transition >= 3 * n {
+                routes.push(vec![transition - 3 * n]);
+            }
+        }
+
+        routes
+    }
+
+    /// Converts routes into transitions of the RPID models.
+    pub fn routes_to_transitions(&self, routes: &[Vec<usize>]) -> Vec<usize> {
+        let n = self.vertices.len();
+
+        routes
+            .iter()
+            .enumerate()
+            .flat_map(|(k, route)| {
+                route.iter().enumerate().map(
+                    move |(j, &i)| {
+                        if k > 0 && j == 0 {
+                            3 * n + i
+                        } else {
+                            i
+                        }
+                    },
+                )
+            })
+            .collect()
+    }
+
+    pub fn print_solution(&self, routes: &[Vec<usize>]) {
+        for (k, route) in routes.iter().enumerate() {
+            let tour = route
                 .iter()
                 .map(|&i| self.vertices[i].to_string())
                 .collect::<Vec<_>>()
-                .join(" ")
-        );
+                .join(" ");
+
+            if self.n_vehicles > 1 {
+                println!("Tour {}: {}", k + 1, tour);
+            } else {
+                println!("Tour: {}", tour);
+            }
+        }
     }
 }
 
@@ -217,6 +532,12 @@ pub struct Args {
         help = "Number of decimal places to round the distances"
     )]
     pub round_to: u32,
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Number of decimal places to round the profits"
+    )]
+    pub profit_round_to: u32,
     #[arg(
         short,
         long,
@@ -224,6 +545,24 @@ pub struct Args {
         help = "Threshold for floating point values"
     )]
     pub epsilon: f64,
+    #[arg(
+        long,
+        action,
+        help = "Improve new solutions found by CABS with local search"
+    )]
+    pub local_search: bool,
+    #[arg(
+        long,
+        default_value_t = 1,
+        help = "Number of vehicles (only one is supported by optw_dypdl)"
+    )]
+    pub vehicles: usize,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Comma-separated ids of customers that must be visited (the input format has no mandatory flag)"
+    )]
+    pub mandatory: Vec<usize>,
 }
 
 pub fn compute_pairwise_shortest_path_costs<T>(weights: &[Vec<T>]) -> Vec<Vec<T>>
@@ -253,3 +592,144 @@ where
 
     distance
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The depot and three customers on a line at 0, 2, 4, and 6,
+    // where customer 3 closes before it can be reached.
+    fn create_instance(n_vehicles: usize) -> RoundedInstance {
+        let positions = [0, 2, 4, 6];
+        let time_windows = vec![vec![(0, 100)], vec![(0, 50)], vec![(0, 50)], vec![(0, 5)]];
+
+        RoundedInstance {
+            n_vehicles,
+            profit_round_to: 0,
+            vertices: vec![0, 1, 2, 3],
+            distances: positions
+                .iter()
+                .map(|&a: &i32| positions.iter().map(|&b| (a - b).abs()).collect())
+                .collect(),
+            service_times: vec![0; 4],
+            profits: vec![0, 10, 20, 30],
+            opening: time_windows.iter().map(|w| w[0].0).collect(),
+            closing: time_windows.iter().map(|w| w[w.len() - 1].1).collect(),
+            time_windows,
+            mandatory: vec![false; 4],
+        }
+    }
+
+    #[test]
+    fn validate_accepts_feasible_routes() {
+        assert!(create_instance(1).validate(&[vec![1, 2]], 30));
+        assert!(create_instance(2).validate(&[vec![1], vec![2]], 30));
+    }
+
+    #[test]
+    fn validate_rejects_repeated_customer() {
+        assert!(!create_instance(1).validate(&[vec![1, 2, 1]], 40));
+        assert!(!create_instance(2).validate(&[vec![1], vec![1, 2]], 40));
+    }
+
+    #[test]
+    fn validate_rejects_depot_in_route() {
+        assert!(!create_instance(1).validate(&[vec![1, 0, 2]], 30));
+    }
+
+    #[test]
+    fn validate_rejects_invalid_index() {
+        assert!(!create_instance(1).validate(&[vec![1, 4]], 10));
+    }
+
+    #[test]
+    fn validate_rejects_missed_time_window() {
+        assert!(!create_instance(1).validate(&[vec![1, 2, 3]], 60));
+    }
+
+    #[test]
+    fn validate_rejects_wrong_profit() {
+        assert!(!create_instance(1).validate(&[vec![1, 2]], 31));
+    }
+
+    #[test]
+    fn validate_rejects_unvisited_mandatory_customer() {
+        let mut instance = create_instance(1);
+        instance.mandatory[2] = true;
+
+        assert!(instance.validate(&[vec![2]], 20));
+        assert!(!instance.validate(&[vec![1]], 10));
+        assert!(!instance.validate(&[], 0));
+    }
+
+    #[test]
+    fn validate_waits_for_later_time_window() {
+        let mut instance = create_instance(1);
+        instance.time_windows[3] = vec![(0, 5), (10, 20)];
+        instance.closing[3] = 20;
+
+        // Customer 3 is reached at time 6 after customers 1 and 2, and the service starts at 10.
+        assert!(instance.validate(&[vec![1, 2, 3]], 60));
+    }
+
+    #[test]
+    fn get_start_time_selects_first_open_window() {
+        let mut instance = create_instance(1);
+        instance.time_windows[1] = vec![(5, 10), (20, 30)];
+
+        assert_eq!(instance.get_start_time(1, 0), Some(5));
+        assert_eq!(instance.get_start_time(1, 7), Some(7));
+        assert_eq!(instance.get_start_time(1, 10), Some(10));
+        assert_eq!(instance.get_start_time(1, 11), Some(20));
+        assert_eq!(instance.get_start_time(1, 30), Some(30));
+        assert_eq!(instance.get_start_time(1, 31), None);
+    }
+
+    #[test]
+    fn parse_time_windows_reads_windows_after_combinations() {
+        let windows = Instance::parse_time_windows(&["1", "2", "1", "2", "22", "58", "60", "92"]);
+
+        assert_eq!(windows.unwrap(), vec![(22.0, 58.0), (60.0, 92.0)]);
+        assert_eq!(
+            Instance::parse_time_windows(&["0", "0", "0", "119"]).unwrap(),
+            vec![(0.0, 119.0)]
+        );
+    }
+
+    #[test]
+    fn parse_time_windows_rejects_malformed_windows() {
+        assert!(Instance::parse_time_windows(&["1", "1", "1", "22", "58", "60"]).is_err());
+        assert!(Instance::parse_time_windows(&["1", "3", "1", "22", "58"]).is_err());
+        assert!(Instance::parse_time_windows(&["0", "0"]).is_err());
+        assert!(Instance::parse_time_windows(&["0"]).is_err());
+    }
+
+    #[test]
+    fn load_marks_mandatory_customers() {
+        let filename = std::env::temp_dir().join("optw_load_marks_mandatory_customers.txt");
+        std::fs::write(
+            &filename,
+            "1 1 2 1\n\
+             0 0\n\
+             0 0 0 0 0 0 0 0 100\n\
+             1 3 4 1 10 1 1 1 0 20\n\
+             2 6 8 2 20 1 1 1 10 20 40 60\n",
+        )
+        .unwrap();
+        let filename = filename.to_str().unwrap();
+
+        let instance = Instance::load(filename, &[2]).unwrap();
+        assert_eq!(instance.mandatory, vec![false, false, true]);
+        assert_eq!(
+            instance.time_windows,
+            vec![
+                vec![(0.0, 100.0)],
+                vec![(0.0, 20.0)],
+                vec![(10.0, 20.0), (40.0, 60.0)]
+            ]
+        );
+
+        assert!(Instance::load(filename, &[0]).is_err());
+        assert!(Instance::load(filename, &[3]).is_err());
+    }
+}
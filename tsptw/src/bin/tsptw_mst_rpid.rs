@@ -2,7 +2,9 @@ use clap::Parser;
 use fixedbitset::FixedBitSet;
 use rpid::prelude::*;
 use rpid::{algorithms, io, solvers, timer::Timer};
+use std::cell::Cell;
 use std::cmp::{self, Ordering};
+use std::rc::Rc;
 use tsptw::{Args, Instance, SimplificationChoice, SolverChoice};
 
 struct Tsptw {
@@ -207,6 +209,25 @@ fn main() {
         ..Default::default()
     };
     let solution = match args.solver {
+        SolverChoice::Cabs if args.local_search => {
+            let cabs_parameters = CabsParameters::default();
+            let primal_bound = Rc::new(Cell::new(None));
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let mut solver = io_util::create_cabs_with_shared_primal_bound(
+                tsptw,
+                parameters,
+                cabs_parameters,
+                primal_bound.clone(),
+            );
+            io_util::run_solver_with_local_search_and_dump_solution_history(
+                &mut solver,
+                &args.history,
+                OptimizationMode::Minimization,
+                &primal_bound,
+                |tour| instance.improve_tour(tour, args.minimize_makespan),
+            )
+            .unwrap()
+        }
         SolverChoice::Cabs => {
             let cabs_parameters = CabsParameters::default();
             println!("Preparing time: {}s", timer.get_elapsed_time());
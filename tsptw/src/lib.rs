@@ -44,12 +44,35 @@ impl Instance {
     }
 
     fn validate_inner(&self, tour: &[usize], cost: i32, minimize_makespan: bool) -> bool {
-        if tour.len() != self.a.len() {
-            println!("Invalid tour length: {}", tour.len());
+        let Some((recomputed_cost, time)) = self.evaluate_tour(tour, true) else {
+            return false;
+        };
+
+        if minimize_makespan {
+            if cost != time {
+                println!("Invalid makespan: {} != {}", cost, time);
+
+                return false;
+            }
+        } else if cost != recomputed_cost {
+            println!("Invalid cost: {} != {}", cost, recomputed_cost);
 
             return false;
         }
 
+        true
+    }
+
+    /// Returns the cost and the makespan of a tour, or `None` if the tour is infeasible.
+    fn evaluate_tour(&self, tour: &[usize], verbose: bool) -> Option<(i32, i32)> {
+        if tour.len() != self.a.len() {
+            if verbose {
+                println!("Invalid tour length: {}", tour.len());
+            }
+
+            return None;
+        }
+
         let mut time = 0;
         let mut current = 0;
         let mut visited = vec![false; self.a.len()];
@@ -57,49 +80,81 @@ impl Instance {
 
         for &next in tour.iter() {
             if next >= self.a.len() {
-                println!("Invalid node index: {}", next);
+                if verbose {
+                    println!("Invalid node index: {}", next);
+                }
 
-                return false;
+                return None;
             }
 
             if visited[next] {
-                println!("Visited node twice: {}", next);
+                if verbose {
+                    println!("Visited node twice: {}", next);
+                }
 
-                return false;
+                return None;
             }
 
             if let Some(distance) = self.c[current][next] {
                 time = cmp::max(time + distance, self.a[next]);
                 recomputed_cost += distance;
             } else {
-                println!("Invalid edge: {} -> {}", current, next);
+                if verbose {
+                    println!("Invalid edge: {} -> {}", current, next);
+                }
 
-                return false;
+                return None;
             }
 
             if time > self.b[next] {
-                println!("Time window violation: {} at {}", next, time);
+                if verbose {
+                    println!("Time window violation: {} at {}", next, time);
+                }
 
-                return false;
+                return None;
             }
 
             current = next;
             visited[next] = true;
         }
 
-        if minimize_makespan {
-            if cost != time {
-                println!("Invalid makespan: {} != {}", cost, time);
+        Some((recomputed_cost, time))
+    }
 
-                return false;
-            }
-        } else if cost != recomputed_cost {
-            println!("Invalid cost: {} != {}", cost, recomputed_cost);
+    /// Improves a tour by 2-opt and or-opt moves keeping the time windows satisfied.
+    ///
+    /// The tour may or may not end with the depot, and the improved tour has the same form.
+    /// Returns the improved cost and tour if an improvement is found.
+    pub fn improve_tour(
+        &self,
+        tour: &[usize],
+        minimize_makespan: bool,
+    ) -> Option<(i32, Vec<usize>)> {
+        let ends_with_depot = tour.last() == Some(&0);
+        let customers = if ends_with_depot {
+            &tour[..tour.len() - 1]
+        } else {
+            tour
+        };
+
+        let (cost, mut customers) =
+            io_util::improve_sequence_by_local_search(customers, |customers| {
+                let mut tour = customers.to_vec();
+                tour.push(0);
+                let (cost, makespan) = self.evaluate_tour(&tour, false)?;
+
+                if minimize_makespan {
+                    Some(makespan)
+                } else {
+                    Some(cost)
+                }
+            })?;
 
-            return false;
+        if ends_with_depot {
+            customers.push(0);
         }
 
-        true
+        Some((cost, customers))
     }
 
     pub fn simplify(&mut self, expensive_detection: bool) {
@@ -189,6 +244,12 @@ pub enum SimplificationChoice {
     Expensive,
 }
 
+#[derive(Debug, Clone, ValueEnum)]
+pub enum BoundChoice {
+    Assignment,
+    MinEdge,
+}
+
 #[derive(Debug, Parser)]
 pub struct Args {
     #[arg(help = "Input file")]
@@ -203,4 +264,12 @@ pub struct Args {
     pub simplification_level: SimplificationChoice,
     #[arg(long, short, action, help = "Minimize makespan")]
     pub minimize_makespan: bool,
+    #[arg(long, value_enum, default_value_t = BoundChoice::Assignment, help = "Dual bound of the assignment-based model")]
+    pub bound: BoundChoice,
+    #[arg(
+        long,
+        action,
+        help = "Improve new solutions found by CABS with local search"
+    )]
+    pub local_search: bool,
 }
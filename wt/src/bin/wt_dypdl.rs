@@ -4,6 +4,7 @@ use dypdl_heuristic_search::{
     create_caasdy, create_dual_bound_cabs, BeamSearchParameters, CabsParameters, FEvaluatorType,
     Parameters,
 };
+use io_util::InstanceResult;
 use rpid::timer::Timer;
 use std::rc::Rc;
 use wt::{Args, Instance, SolverChoice};
@@ -15,11 +16,31 @@ use tikv_jemallocator::Jemalloc;
 #[global_allocator]
 static GLOBAL: Jemalloc = Jemalloc;
 
-fn main() {
+fn solve(
+    instance: Instance,
+    args: &Args,
+    history: &str,
+    dump_preprocessing: Option<&str>,
+) -> Option<InstanceResult<i32>> {
     let timer = Timer::default();
-    let args = Args::parse();
 
-    let instance = Instance::read_from_file(&args.input_file).unwrap();
+    if instance.n_machines > 1 || instance.has_release_dates() {
+        println!("Use wt_parallel_rpid for multiple machines or release dates");
+
+        return None;
+    }
+
+    if instance.has_setup_times() {
+        println!("Use wt_setup_rpid for setup times");
+
+        return None;
+    }
+
+    if let Some(filename) = dump_preprocessing {
+        let graph = instance.extract_precedence_graph();
+        graph.print_summary();
+        graph.dump(filename).unwrap();
+    }
 
     let mut model = Model::default();
 
@@ -33,7 +54,12 @@ fn main() {
     let processing_times = model
         .add_table_1d("processing_times", instance.processing_times.clone())
         .unwrap();
+    let precedence_timer = Timer::default();
     let (predecessors, _) = instance.extract_precedence();
+    println!(
+        "Precedence inference time: {}s",
+        precedence_timer.get_elapsed_time()
+    );
     let predecessors = predecessors
         .iter()
         .map(|p| {
@@ -96,11 +122,10 @@ fn main() {
         }
     };
 
-    let solution =
-        io_util::run_solver_and_dump_solution_history(&mut solver, &args.history).unwrap();
+    let solution = io_util::run_solver_and_dump_solution_history(&mut solver, history).unwrap();
     io_util::print_solution_statistics(&solution);
 
-    if let Some(cost) = solution.cost {
+    let is_valid = if let Some(cost) = solution.cost {
         let sequence = solution
             .transitions
             .iter()
@@ -112,10 +137,33 @@ fn main() {
             .map(|s| s.parse().unwrap())
             .collect::<Vec<_>>();
 
-        if instance.validate(&sequence, cost) {
+        let is_valid = instance.validate(&sequence, cost);
+
+        if is_valid {
             println!("The solution is valid.");
         } else {
             println!("The solution is invalid.");
         }
-    }
+
+        is_valid
+    } else {
+        false
+    };
+
+    Some(InstanceResult {
+        cost: solution.cost,
+        bound: solution.best_bound,
+        is_optimal: solution.is_optimal,
+        is_valid,
+        time: solution.time,
+    })
+}
+
+fn main() {
+    let args = Args::parse();
+
+    wt::solve_instances(&args, |instance, history, dump_preprocessing| {
+        solve(instance, &args, history, dump_preprocessing)
+    })
+    .unwrap();
 }
@@ -0,0 +1,234 @@
+use clap::Parser;
+use fixedbitset::FixedBitSet;
+use io_util::InstanceResult;
+use rpid::prelude::*;
+use rpid::{io, solvers, timer::Timer};
+use std::cmp::{self, Ordering};
+use wt::{Args, BoundChoice, Instance, SolverChoice};
+
+#[cfg(not(target_env = "msvc"))]
+use tikv_jemallocator::Jemalloc;
+
+#[cfg(not(target_env = "msvc"))]
+#[global_allocator]
+static GLOBAL: Jemalloc = Jemalloc;
+
+struct ParallelWt {
+    instance: Instance,
+    predecessors: Vec<FixedBitSet>,
+    successors: Vec<FixedBitSet>,
+    bound: BoundChoice,
+}
+
+impl ParallelWt {
+    fn new(instance: Instance, bound: BoundChoice) -> Self {
+        let timer = Timer::default();
+        let (predecessors, successors) = instance.extract_precedence_closure();
+        println!("Precedence inference time: {}s", timer.get_elapsed_time());
+
+        Self {
+            instance,
+            predecessors,
+            successors,
+            bound,
+        }
+    }
+}
+
+// Each job is started as early as possible on the machine that becomes available first.
+// Since the jobs of a schedule can be sorted by their start times, the start times are non-decreasing.
+#[derive(Clone)]
+struct ParallelWtState {
+    scheduled: FixedBitSet,
+    availability: Vec<i32>,
+    start: i32,
+}
+
+impl Dp for ParallelWt {
+    type State = ParallelWtState;
+    type CostType = i32;
+
+    fn get_target(&self) -> Self::State {
+        Self::State {
+            scheduled: FixedBitSet::with_capacity(self.instance.processing_times.len()),
+            availability: vec![0; self.instance.n_machines],
+            start: 0,
+        }
+    }
+
+    fn get_successors(
+        &self,
+        state: &Self::State,
+    ) -> impl IntoIterator<Item = (Self::State, Self::CostType, usize)> {
+        state.scheduled.zeroes().filter_map(move |i| {
+            if !self.predecessors[i].is_subset(&state.scheduled) {
+                return None;
+            }
+
+            let start = cmp::max(state.availability[0], self.instance.release_dates[i]);
+
+            if start < state.start {
+                return None;
+            }
+
+            let mut scheduled = state.scheduled.clone();
+            scheduled.insert(i);
+            let time = start + self.instance.processing_times[i];
+
+            // The availability is kept sorted to break symmetry between machines.
+            let mut availability = state.availability.clone();
+            availability[0] = time;
+            availability.sort_unstable();
+
+            let weight = self.instance.weights[i] * cmp::max(0, time - self.instance.deadlines[i]);
+
+            Some((
+                ParallelWtState {
+                    scheduled,
+                    availability,
+                    start,
+                },
+                weight,
+                i,
+            ))
+        })
+    }
+
+    fn get_base_cost(&self, state: &Self::State) -> Option<Self::CostType> {
+        if state.scheduled.is_full() {
+            Some(0)
+        } else {
+            None
+        }
+    }
+}
+
+impl Dominance for ParallelWt {
+    type State = ParallelWtState;
+    type Key = FixedBitSet;
+
+    fn get_key(&self, state: &Self::State) -> Self::Key {
+        state.scheduled.clone()
+    }
+
+    fn compare(&self, a: &Self::State, b: &Self::State) -> Option<Ordering> {
+        let mut ordering = b.start.cmp(&a.start);
+
+        for o in b
+            .availability
+            .iter()
+            .zip(a.availability.iter())
+            .map(|(b, a)| b.cmp(a))
+        {
+            match (ordering, o) {
+                (_, Ordering::Equal) => {}
+                (Ordering::Equal, o) => ordering = o,
+                (ordering, o) if ordering != o => return None,
+                _ => {}
+            }
+        }
+
+        Some(ordering)
+    }
+}
+
+impl Bound for ParallelWt {
+    type State = ParallelWtState;
+    type CostType = i32;
+
+    fn get_dual_bound(&self, state: &Self::State) -> Option<Self::CostType> {
+        let mut unscheduled = state.scheduled.clone();
+        unscheduled.toggle_range(..);
+        let time = cmp::max(state.availability[0], state.start);
+
+        // The single-machine bounds are still valid with release dates since they only delay jobs.
+        match self.bound {
+            BoundChoice::Zero => Some(0),
+            _ if self.instance.n_machines == 1 => self.instance.compute_dual_bound(
+                &self.bound,
+                &unscheduled,
+                time,
+                &self.predecessors,
+                &self.successors,
+            ),
+            _ => Some(self.instance.compute_individual_bound(&unscheduled, time)),
+        }
+    }
+}
+
+fn solve(
+    instance: Instance,
+    args: &Args,
+    history: &str,
+    dump_preprocessing: Option<&str>,
+) -> Option<InstanceResult<i32>> {
+    let timer = Timer::default();
+    println!("Number of machines: {}", instance.n_machines);
+
+    if instance.has_setup_times() {
+        println!("Use wt_setup_rpid for setup times");
+
+        return None;
+    }
+
+    if let Some(filename) = dump_preprocessing {
+        let graph = instance.extract_precedence_graph();
+        graph.print_summary();
+        graph.dump(filename).unwrap();
+    }
+
+    let wt = ParallelWt::new(instance.clone(), args.bound.clone());
+
+    let parameters = SearchParameters {
+        time_limit: Some(args.time_limit),
+        ..Default::default()
+    };
+
+    let solution = match args.solver {
+        SolverChoice::Cabs => {
+            let cabs_parameters = CabsParameters::default();
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let mut solver = solvers::create_cabs(wt, parameters, cabs_parameters);
+            io::run_solver_and_dump_solution_history(&mut solver, history).unwrap()
+        }
+        SolverChoice::Astar => {
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let mut solver = solvers::create_astar(wt, parameters);
+            io::run_solver_and_dump_solution_history(&mut solver, history).unwrap()
+        }
+    };
+    io::print_solution_statistics(&solution);
+
+    let is_valid = if let Some(cost) = solution.cost {
+        instance.print_solution(&solution.transitions);
+
+        let is_valid = instance.validate(&solution.transitions, cost);
+
+        if is_valid {
+            println!("The solution is valid.");
+        } else {
+            println!("The solution is invalid.");
+        }
+
+        is_valid
+    } else {
+        false
+    };
+
+    Some(InstanceResult {
+        cost: solution.cost,
+        bound: solution.best_bound,
+        is_optimal: solution.is_optimal,
+        is_valid,
+        time: solution.time,
+    })
+}
+
+fn main() {
+    let args = Args::parse();
+
+    wt::solve_instances(&args, |instance, history, dump_preprocessing| {
+        solve(instance, &args, history, dump_preprocessing)
+    })
+    .unwrap();
+}
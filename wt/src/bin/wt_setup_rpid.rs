@@ -0,0 +1,207 @@
+use clap::Parser;
+use fixedbitset::FixedBitSet;
+use io_util::InstanceResult;
+use rpid::prelude::*;
+use rpid::{io, solvers, timer::Timer};
+use std::cmp::{self, Ordering};
+use wt::{Args, BoundChoice, Instance, SolverChoice};
+
+#[cfg(not(target_env = "msvc"))]
+use tikv_jemallocator::Jemalloc;
+
+#[cfg(not(target_env = "msvc"))]
+#[global_allocator]
+static GLOBAL: Jemalloc = Jemalloc;
+
+struct SetupWt {
+    instance: Instance,
+    predecessors: Vec<FixedBitSet>,
+    successors: Vec<FixedBitSet>,
+    bound: BoundChoice,
+}
+
+#[derive(Clone)]
+struct SetupWtState {
+    scheduled: FixedBitSet,
+    time: i32,
+    last: Option<usize>,
+}
+
+impl SetupWt {
+    fn new(instance: Instance, bound: BoundChoice) -> Self {
+        let timer = Timer::default();
+        let (predecessors, successors) = instance.extract_precedence_closure();
+        println!("Precedence inference time: {}s", timer.get_elapsed_time());
+
+        Self {
+            instance,
+            predecessors,
+            successors,
+            bound,
+        }
+    }
+}
+
+impl Dp for SetupWt {
+    type State = SetupWtState;
+    type CostType = i32;
+
+    fn get_target(&self) -> Self::State {
+        Self::State {
+            scheduled: FixedBitSet::with_capacity(self.instance.processing_times.len()),
+            time: 0,
+            last: None,
+        }
+    }
+
+    fn get_successors(
+        &self,
+        state: &Self::State,
+    ) -> impl IntoIterator<Item = (Self::State, Self::CostType, usize)> {
+        state.scheduled.zeroes().filter_map(move |i| {
+            if self.predecessors[i].is_subset(&state.scheduled) {
+                let mut scheduled = state.scheduled.clone();
+                scheduled.insert(i);
+                let time = cmp::max(state.time, self.instance.release_dates[i])
+                    + self.instance.get_setup_time(state.last, i)
+                    + self.instance.processing_times[i];
+
+                let weight =
+                    self.instance.weights[i] * cmp::max(0, time - self.instance.deadlines[i]);
+
+                let successor = SetupWtState {
+                    scheduled,
+                    time,
+                    last: Some(i),
+                };
+
+                Some((successor, weight, i))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn get_base_cost(&self, state: &Self::State) -> Option<Self::CostType> {
+        if state.scheduled.is_full() {
+            Some(0)
+        } else {
+            None
+        }
+    }
+}
+
+impl Dominance for SetupWt {
+    type State = SetupWtState;
+    type Key = (FixedBitSet, Option<usize>);
+
+    // The setup time of the next job depends on the last job.
+    fn get_key(&self, state: &Self::State) -> Self::Key {
+        (state.scheduled.clone(), state.last)
+    }
+
+    fn compare(&self, a: &Self::State, b: &Self::State) -> Option<Ordering> {
+        Some(b.time.cmp(&a.time))
+    }
+}
+
+impl Bound for SetupWt {
+    type State = SetupWtState;
+    type CostType = i32;
+
+    // The single-machine bounds are still valid since setup times and release dates only delay jobs.
+    fn get_dual_bound(&self, state: &Self::State) -> Option<Self::CostType> {
+        let mut unscheduled = state.scheduled.clone();
+        unscheduled.toggle_range(..);
+
+        self.instance.compute_dual_bound(
+            &self.bound,
+            &unscheduled,
+            state.time,
+            &self.predecessors,
+            &self.successors,
+        )
+    }
+}
+
+fn solve(
+    instance: Instance,
+    args: &Args,
+    history: &str,
+    dump_preprocessing: Option<&str>,
+) -> Option<InstanceResult<i32>> {
+    let timer = Timer::default();
+
+    if instance.n_machines > 1 {
+        println!("Use wt_parallel_rpid for multiple machines");
+
+        return None;
+    }
+
+    if let Some(filename) = dump_preprocessing {
+        let graph = instance.extract_precedence_graph();
+        graph.print_summary();
+        graph.dump(filename).unwrap();
+    }
+
+    let wt = SetupWt::new(instance.clone(), args.bound.clone());
+
+    let parameters = SearchParameters {
+        time_limit: Some(args.time_limit),
+        ..Default::default()
+    };
+
+    let solution = match args.solver {
+        SolverChoice::Cabs => {
+            let cabs_parameters = CabsParameters::default();
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let mut solver = solvers::create_cabs(wt, parameters, cabs_parameters);
+            io::run_solver_and_dump_solution_history(&mut solver, history).unwrap()
+        }
+        SolverChoice::Astar => {
+            println!("Preparing time: {}s", timer.get_elapsed_time());
+            let mut solver = solvers::create_astar(wt, parameters);
+            io::run_solver_and_dump_solution_history(&mut solver, history).unwrap()
+        }
+    };
+    io::print_solution_statistics(&solution);
+
+    let is_valid = if let Some(cost) = solution.cost {
+        let schedule = solution
+            .transitions
+            .iter()
+            .map(|t| format!("{}", t))
+            .collect::<Vec<_>>()
+            .join(" ");
+        println!("Schedule: {}", schedule);
+
+        let is_valid = instance.validate(&solution.transitions, cost);
+
+        if is_valid {
+            println!("The solution is valid.");
+        } else {
+            println!("The solution is invalid.");
+        }
+
+        is_valid
+    } else {
+        false
+    };
+
+    Some(InstanceResult {
+        cost: solution.cost,
+        bound: solution.best_bound,
+        is_optimal: solution.is_optimal,
+        is_valid,
+        time: solution.time,
+    })
+}
+
+fn main() {
+    let args = Args::parse();
+
+    wt::solve_instances(&args, |instance, history, dump_preprocessing| {
+        solve(instance, &args, history, dump_preprocessing)
+    })
+    .unwrap();
+}
@@ -1,9 +1,10 @@
 use clap::Parser;
 use fixedbitset::FixedBitSet;
+use io_util::InstanceResult;
 use rpid::prelude::*;
 use rpid::{io, solvers, timer::Timer};
 use std::cmp;
-use wt::{Args, Instance, SolverChoice};
+use wt::{Args, BoundChoice, Instance, SolverChoice};
 
 #[cfg(not(target_env = "msvc"))]
 use tikv_jemallocator::Jemalloc;
@@ -15,6 +16,8 @@ static GLOBAL: Jemalloc = Jemalloc;
 struct Wt {
     instance: Instance,
     predecessors: Vec<FixedBitSet>,
+    successors: Vec<FixedBitSet>,
+    bound: BoundChoice,
 }
 
 #[derive(Clone)]
@@ -23,13 +26,17 @@ struct WtState {
     time: i32,
 }
 
-impl From<Instance> for Wt {
-    fn from(instance: Instance) -> Self {
-        let (predecessors, _) = instance.extract_precedence();
+impl Wt {
+    fn new(instance: Instance, bound: BoundChoice) -> Self {
+        let timer = Timer::default();
+        let (predecessors, successors) = instance.extract_precedence_closure();
+        println!("Precedence inference time: {}s", timer.get_elapsed_time());
 
         Self {
             instance,
             predecessors,
+            successors,
+            bound,
         }
     }
 }
@@ -87,17 +94,47 @@ impl Bound for Wt {
     type State = WtState;
     type CostType = i32;
 
-    fn get_dual_bound(&self, _: &Self::State) -> Option<Self::CostType> {
-        Some(0)
+    fn get_dual_bound(&self, state: &Self::State) -> Option<Self::CostType> {
+        let mut unscheduled = state.scheduled.clone();
+        unscheduled.toggle_range(..);
+
+        self.instance.compute_dual_bound(
+            &self.bound,
+            &unscheduled,
+            state.time,
+            &self.predecessors,
+            &self.successors,
+        )
     }
 }
 
-fn main() {
+fn solve(
+    instance: Instance,
+    args: &Args,
+    history: &str,
+    dump_preprocessing: Option<&str>,
+) -> Option<InstanceResult<i32>> {
     let timer = Timer::default();
-    let args = Args::parse();
 
-    let instance = Instance::read_from_file(&args.input_file).unwrap();
-    let wt = Wt::from(instance.clone());
+    if instance.n_machines > 1 || instance.has_release_dates() {
+        println!("Use wt_parallel_rpid for multiple machines or release dates");
+
+        return None;
+    }
+
+    if instance.has_setup_times() {
+        println!("Use wt_setup_rpid for setup times");
+
+        return None;
+    }
+
+    if let Some(filename) = dump_preprocessing {
+        let graph = instance.extract_precedence_graph();
+        graph.print_summary();
+        graph.dump(filename).unwrap();
+    }
+
+    let wt = Wt::new(instance.clone(), args.bound.clone());
 
     let parameters = SearchParameters {
         time_limit: Some(args.time_limit),
@@ -109,17 +146,17 @@ fn main() {
             let cabs_parameters = CabsParameters::default();
             println!("Preparing time: {}s", timer.get_elapsed_time());
             let mut solver = solvers::create_cabs(wt, parameters, cabs_parameters);
-            io::run_solver_and_dump_solution_history(&mut solver, &args.history).unwrap()
+            io::run_solver_and_dump_solution_history(&mut solver, history).unwrap()
         }
         SolverChoice::Astar => {
             println!("Preparing time: {}s", timer.get_elapsed_time());
             let mut solver = solvers::create_astar(wt, parameters);
-            io::run_solver_and_dump_solution_history(&mut solver, &args.history).unwrap()
+            io::run_solver_and_dump_solution_history(&mut solver, history).unwrap()
         }
     };
     io::print_solution_statistics(&solution);
 
-    if let Some(cost) = solution.cost {
+    let is_valid = if let Some(cost) = solution.cost {
         let schedule = solution
             .transitions
             .iter()
@@ -128,10 +165,33 @@ fn main() {
             .join(" ");
         println!("Schedule: {}", schedule);
 
-        if instance.validate(&solution.transitions, cost) {
+        let is_valid = instance.validate(&solution.transitions, cost);
+
+        if is_valid {
             println!("The solution is valid.");
         } else {
             println!("The solution is invalid.");
         }
-    }
+
+        is_valid
+    } else {
+        false
+    };
+
+    Some(InstanceResult {
+        cost: solution.cost,
+        bound: solution.best_bound,
+        is_optimal: solution.is_optimal,
+        is_valid,
+        time: solution.time,
+    })
+}
+
+fn main() {
+    let args = Args::parse();
+
+    wt::solve_instances(&args, |instance, history, dump_preprocessing| {
+        solve(instance, &args, history, dump_preprocessing)
+    })
+    .unwrap();
 }
@@ -1,39 +1,256 @@
 use clap::{Parser, ValueEnum};
 use fixedbitset::FixedBitSet;
+use io_util::{AssignmentSolution, InstanceResult, PrecedenceGraph};
 use rpid::io;
 use std::cmp;
-use std::collections::VecDeque;
 use std::error::Error;
 use std::fs;
 
+type PrecedenceArc = (usize, usize, &'static str);
+
+// Direct precedence inferred by the Kanet conditions and its transitive closure.
+struct InferredPrecedence {
+    predecessors: Vec<FixedBitSet>,
+    successors: Vec<FixedBitSet>,
+    ancestors: Vec<FixedBitSet>,
+    descendants: Vec<FixedBitSet>,
+    arcs: Vec<PrecedenceArc>,
+}
+
 #[derive(Clone, Debug)]
 pub struct Instance {
     pub processing_times: Vec<i32>,
     pub deadlines: Vec<i32>,
     pub weights: Vec<i32>,
+    pub release_dates: Vec<i32>,
+    pub n_machines: usize,
+    pub setup_times: Vec<Vec<i32>>,
+    pub initial_setup_times: Vec<i32>,
 }
 
 impl Instance {
-    pub fn read_from_file(filename: &str) -> Result<Self, Box<dyn Error>> {
+    /// Reads an instance from a file.
+    ///
+    /// The file starts with the number of jobs, followed by the number of identical machines
+    /// if `has_machines` is true (a single machine otherwise).
+    /// Then, the processing time, the deadline, and the weight of each job are given,
+    /// followed by the release date if `has_release_dates` is true.
+    /// Values after the last job are ignored.
+    pub fn read_from_file(
+        filename: &str,
+        has_machines: bool,
+        has_release_dates: bool,
+    ) -> Result<Self, Box<dyn Error>> {
         let file = fs::read_to_string(filename)?;
         let mut digits = file.split_whitespace();
 
         let n = digits
             .next()
             .ok_or("failed to parse the number of jobs".to_owned())?
-            .parse()?;
-        let matrix = io::read_matrix(&mut digits, n, 3)?;
+            .parse::<usize>()?;
+
+        if n == 0 {
+            return Err("no job".into());
+        }
+
+        let n_machines = if has_machines {
+            digits
+                .next()
+                .ok_or("failed to parse the number of machines".to_owned())?
+                .parse()?
+        } else {
+            1
+        };
+
+        let n_columns = if has_release_dates { 4 } else { 3 };
+        let matrix = io::read_matrix(&mut digits, n, n_columns)?;
         let processing_time = matrix.iter().map(|row| row[0]).collect();
         let deadline = matrix.iter().map(|row| row[1]).collect();
         let weights = matrix.iter().map(|row| row[2]).collect();
+        let release_dates = matrix
+            .iter()
+            .map(|row| row.get(3).copied().unwrap_or(0))
+            .collect();
 
         Ok(Self {
             processing_times: processing_time,
             deadlines: deadline,
             weights,
+            release_dates,
+            n_machines,
+            setup_times: vec![],
+            initial_setup_times: vec![0; n],
         })
     }
 
+    /// Reads instances from a file in the OR-Library format.
+    ///
+    /// The file contains a sequence of instances with `n` jobs each,
+    /// where each instance lists the processing times, the weights, and then the due dates.
+    pub fn read_all_from_orlib_file(filename: &str, n: usize) -> Result<Vec<Self>, Box<dyn Error>> {
+        if n == 0 {
+            return Err("no job".into());
+        }
+
+        let file = fs::read_to_string(filename)?;
+        let digits = file
+            .split_whitespace()
+            .map(|digit| digit.parse())
+            .collect::<Result<Vec<i32>, _>>()?;
+
+        if digits.is_empty() || digits.len() % (3 * n) != 0 {
+            return Err(format!(
+                "the number of values {} is not a multiple of 3 * {}",
+                digits.len(),
+                n
+            )
+            .into());
+        }
+
+        let instances = digits
+            .chunks(3 * n)
+            .map(|chunk| Self {
+                processing_times: chunk[..n].to_vec(),
+                deadlines: chunk[2 * n..].to_vec(),
+                weights: chunk[n..2 * n].to_vec(),
+                release_dates: vec![0; n],
+                n_machines: 1,
+                setup_times: vec![],
+                initial_setup_times: vec![0; n],
+            })
+            .collect();
+
+        Ok(instances)
+    }
+
+    /// Reads the selected instances with their indices starting from 1
+    /// and overrides the number of machines if given.
+    ///
+    /// If `orlib_jobs` is given, the file is read in the OR-Library format with that number of jobs;
+    /// otherwise, the file contains a single instance,
+    /// which gives the number of machines after the number of jobs if `machines_in_file` is true.
+    /// All instances are selected if `index` is `None`.
+    /// If `setup_times` is given, the setup times are read from that file for all instances.
+    pub fn load(
+        filename: &str,
+        orlib_jobs: Option<usize>,
+        index: Option<usize>,
+        machines: Option<usize>,
+        machines_in_file: bool,
+        release_dates_in_file: bool,
+        setup_times: Option<&str>,
+    ) -> Result<Vec<(usize, Self)>, Box<dyn Error>> {
+        let mut instances = if let Some(n) = orlib_jobs {
+            Self::read_all_from_orlib_file(filename, n)?
+        } else {
+            vec![Self::read_from_file(
+                filename,
+                machines_in_file,
+                release_dates_in_file,
+            )?]
+        };
+
+        if let Some(setup_times) = setup_times {
+            for instance in &mut instances {
+                instance.read_setup_times_from_file(setup_times)?;
+            }
+        }
+
+        if let Some(index) = index {
+            if index == 0 || index > instances.len() {
+                return Err(format!("instance {} is not in 1..={}", index, instances.len()).into());
+            }
+        }
+
+        let instances = instances
+            .into_iter()
+            .enumerate()
+            .map(|(i, instance)| (i + 1, instance))
+            .filter(|(i, _)| index.is_none_or(|index| index == *i))
+            .map(|(i, mut instance)| {
+                if let Some(machines) = machines {
+                    instance.n_machines = machines;
+                }
+
+                (i, instance)
+            })
+            .collect();
+
+        Ok(instances)
+    }
+
+    /// Reads sequence-dependent setup times from a file.
+    ///
+    /// The file contains an n x n matrix, where the j-th value of the i-th row is the setup time
+    /// when job j is processed just after job i.
+    /// The matrix can be preceded by a row giving the setup time of each job processed first on a machine.
+    pub fn read_setup_times_from_file(&mut self, filename: &str) -> Result<(), Box<dyn Error>> {
+        let file = fs::read_to_string(filename)?;
+        let mut digits = file.split_whitespace();
+        let n = self.processing_times.len();
+        let n_values = file.split_whitespace().count();
+
+        if n_values == (n + 1) * n {
+            self.initial_setup_times = io::read_matrix(&mut digits, 1, n)?.pop().unwrap();
+        } else if n_values != n * n {
+            return Err(format!(
+                "unexpected number of setup times {} for {} jobs",
+                n_values, n
+            )
+            .into());
+        }
+
+        self.setup_times = io::read_matrix(&mut digits, n, n)?;
+
+        Ok(())
+    }
+
+    pub fn has_setup_times(&self) -> bool {
+        !self.setup_times.is_empty()
+    }
+
+    /// Returns the setup time of job `j` processed just after job `i`,
+    /// or first on a machine if `i` is `None`.
+    pub fn get_setup_time(&self, i: Option<usize>, j: usize) -> i32 {
+        match i {
+            Some(i) if self.has_setup_times() => self.setup_times[i][j],
+            Some(_) => 0,
+            None => self.initial_setup_times[j],
+        }
+    }
+
+    pub fn has_release_dates(&self) -> bool {
+        self.release_dates.iter().any(|&r| r > 0)
+    }
+
+    /// Assigns each job in the sequence to the machine that becomes available first,
+    /// starting the job as early as possible.
+    ///
+    /// The setup of a job starts after it is released and the previous job on the machine is completed.
+    /// Returns the completion time of each job in the sequence and the machine processing it.
+    pub fn schedule_sequence(&self, sequence: &[usize]) -> Vec<(i32, usize)> {
+        let mut availability = vec![0; self.n_machines];
+        let mut last = vec![None; self.n_machines];
+
+        sequence
+            .iter()
+            .map(|&j| {
+                let (machine, &time) = availability
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, &time)| time)
+                    .unwrap();
+                let completion_time = cmp::max(time, self.release_dates[j])
+                    + self.get_setup_time(last[machine], j)
+                    + self.processing_times[j];
+                availability[machine] = completion_time;
+                last[machine] = Some(j);
+
+                (completion_time, machine)
+            })
+            .collect()
+    }
+
     pub fn validate(&self, solution: &[usize], cost: i32) -> bool {
         let n = self.processing_times.len();
 
@@ -43,9 +260,7 @@ impl Instance {
             return false;
         }
 
-        let mut time = 0;
         let mut scheduled = vec![false; n];
-        let mut recomputed_cost = 0;
 
         for &j in solution {
             if j >= n {
@@ -60,11 +275,15 @@ impl Instance {
                 return false;
             }
 
-            time += self.processing_times[j];
-            recomputed_cost += self.weights[j] * cmp::max(0, time - self.deadlines[j]);
             scheduled[j] = true;
         }
 
+        let recomputed_cost = solution
+            .iter()
+            .zip(self.schedule_sequence(solution))
+            .map(|(&j, (time, _))| self.weights[j] * cmp::max(0, time - self.deadlines[j]))
+            .sum::<i32>();
+
         if recomputed_cost != cost {
             println!("Invalid cost: {} != {}", cost, recomputed_cost);
 
@@ -74,6 +293,572 @@ impl Instance {
         true
     }
 
+    pub fn print_solution(&self, solution: &[usize]) {
+        let schedule = self.schedule_sequence(solution);
+
+        for k in 0..self.n_machines {
+            let jobs = solution
+                .iter()
+                .zip(schedule.iter())
+                .filter(|(_, &(_, machine))| machine == k)
+                .map(|(j, _)| format!("{}", j))
+                .collect::<Vec<_>>()
+                .join(" ");
+            println!("Machine {}: {}", k + 1, jobs);
+        }
+    }
+
+    // Returns the name of a condition of Kanet (2007) ensuring that `i` precedes `j` if satisfied.
+    //
+    // `p_predecessors` and `p_not_successors` are the total processing times of the predecessors
+    // and the jobs that are not successors.
+    fn check_kanet_conditions(
+        &self,
+        i: usize,
+        j: usize,
+        predecessors: &[FixedBitSet],
+        successors: &[FixedBitSet],
+        p_predecessors: &[i32],
+        p_not_successors: &[i32],
+    ) -> Option<&'static str> {
+        let p_i = self.processing_times[i];
+        let d_i = self.deadlines[i];
+        let w_i = self.weights[i];
+        let p_j = self.processing_times[j];
+        let d_j = self.deadlines[j];
+        let w_j = self.weights[j];
+
+        let p_predecessors_j = p_predecessors[j];
+        let p_common = p_predecessors[i] + p_predecessors[j]
+            - predecessors[i]
+                .intersection(&predecessors[j])
+                .map(|k| self.processing_times[k])
+                .sum::<i32>()
+            + p_i
+            + p_j;
+
+        let k1_common = (w_i - w_j) * p_common;
+
+        if p_i <= p_j
+            && w_i >= w_j
+            && (w_i * d_i <= cmp::max(w_i * d_j, k1_common + w_j * d_j)
+                || w_i * d_i <= k1_common + w_j * (p_predecessors_j + p_j))
+        {
+            return Some("Kanet condition 1");
+        }
+
+        let p_not_successors_i = p_not_successors[i];
+        let p_not_successors_i_or_j = p_not_successors_i
+            - successors[j]
+                .difference(&successors[i])
+                .map(|k| self.processing_times[k])
+                .sum::<i32>();
+
+        let k2_common = (w_j - w_i) * p_not_successors_i;
+
+        if p_i <= p_j
+            && w_i < w_j
+            && w_j * d_j >= k2_common + w_i * d_i
+            && w_j * d_j >= k2_common + w_i * (p_not_successors_i_or_j - p_j)
+        {
+            return Some("Kanet condition 2");
+        }
+
+        if p_i <= p_j
+            && w_i < w_j
+            && w_i * d_i <= (w_i - w_j) * p_not_successors_i + w_j * (p_predecessors_j + p_j)
+            && w_i * p_i <= (w_i - w_j) * (p_not_successors_i - p_predecessors_j) + w_j * p_j
+        {
+            return Some("Kanet condition 3");
+        }
+
+        if w_i >= w_j
+            && w_j * d_j >= cmp::min(w_j * d_i, (w_j - w_i) * p_common + w_i * d_i)
+            && w_j * d_j >= w_j * p_not_successors_i - w_i * p_j
+        {
+            return Some("Kanet condition 4");
+        }
+
+        if w_i >= w_j
+            && w_i * d_i <= (w_i - w_j) * p_common + w_j * (p_predecessors_j + p_j)
+            && w_i * p_j >= w_j * (p_not_successors_i - p_predecessors_j - p_j)
+        {
+            return Some("Kanet condition 5");
+        }
+
+        if w_i < w_j
+            && w_j * d_j >= (w_j - w_i) * p_not_successors_i + w_i * d_i
+            && w_j * d_j >= w_j * p_not_successors_i - w_i * p_j
+        {
+            return Some("Kanet condition 6");
+        }
+
+        if d_j >= p_not_successors_i {
+            return Some("Kanet condition 7");
+        }
+
+        None
+    }
+
+    /// Returns the direct predecessors and successors of each job in the precedence
+    /// inferred by the conditions of Kanet (2007).
+    pub fn extract_precedence(&self) -> (Vec<FixedBitSet>, Vec<FixedBitSet>) {
+        let precedence = self.infer_precedence();
+
+        (precedence.predecessors, precedence.successors)
+    }
+
+    /// Returns the predecessors and successors of each job in the transitive closure
+    /// of the precedence inferred by `extract_precedence`.
+    ///
+    /// The closure allows the same schedules as the direct precedence,
+    /// but it restricts the positions of jobs more in the assignment bound.
+    pub fn extract_precedence_closure(&self) -> (Vec<FixedBitSet>, Vec<FixedBitSet>) {
+        let precedence = self.infer_precedence();
+
+        (precedence.ancestors, precedence.descendants)
+    }
+
+    /// Returns the precedence graph inferred by `extract_precedence`
+    /// with the condition used to infer each arc.
+    pub fn extract_precedence_graph(&self) -> PrecedenceGraph {
+        let precedence = self.infer_precedence();
+
+        PrecedenceGraph {
+            labels: (0..self.processing_times.len())
+                .map(|i| i.to_string())
+                .collect(),
+            arcs: precedence.arcs,
+            removed_edges: vec![],
+        }
+    }
+
+    // Repeatedly adds arcs satisfying a Kanet condition until no arc is added.
+    //
+    // The conditions use the direct predecessors and successors,
+    // while the transitive closure is maintained to avoid cycles.
+    fn infer_precedence(&self) -> InferredPrecedence {
+        let n = self.processing_times.len();
+
+        // The conditions assume that jobs are processed on a single machine without idle and setup times.
+        if self.n_machines > 1 || self.has_release_dates() || self.has_setup_times() {
+            let empty = vec![FixedBitSet::with_capacity(n); n];
+
+            return InferredPrecedence {
+                predecessors: empty.clone(),
+                successors: empty.clone(),
+                ancestors: empty.clone(),
+                descendants: empty,
+                arcs: vec![],
+            };
+        }
+
+        let mut predecessors = vec![FixedBitSet::with_capacity(n); n];
+        let mut successors = vec![FixedBitSet::with_capacity(n); n];
+        let mut ancestors = vec![FixedBitSet::with_capacity(n); n];
+        let mut descendants = vec![FixedBitSet::with_capacity(n); n];
+        let mut p_predecessors = vec![0; n];
+        let mut p_not_successors = vec![self.processing_times.iter().sum::<i32>(); n];
+        let mut arcs = vec![];
+        let mut change = true;
+
+        while change {
+            change = false;
+
+            for i in 0..n {
+                for j in 0..n {
+                    if i == j || predecessors[j].contains(i) || descendants[j].contains(i) {
+                        continue;
+                    }
+
+                    if let Some(condition) = self.check_kanet_conditions(
+                        i,
+                        j,
+                        &predecessors,
+                        &successors,
+                        &p_predecessors,
+                        &p_not_successors,
+                    ) {
+                        predecessors[j].insert(i);
+                        successors[i].insert(j);
+                        p_predecessors[j] += self.processing_times[i];
+                        p_not_successors[i] -= self.processing_times[j];
+                        arcs.push((i, j, condition));
+                        Self::add_to_transitive_closure(i, j, &mut ancestors, &mut descendants);
+                        change = true;
+                    }
+                }
+            }
+        }
+
+        InferredPrecedence {
+            predecessors,
+            successors,
+            ancestors,
+            descendants,
+            arcs,
+        }
+    }
+
+    // Updates the closure with an arc from `i` to `j`,
+    // which makes `j` and its descendants reachable from `i` and its ancestors.
+    fn add_to_transitive_closure(
+        i: usize,
+        j: usize,
+        ancestors: &mut [FixedBitSet],
+        descendants: &mut [FixedBitSet],
+    ) {
+        let mut from = ancestors[i].clone();
+        from.insert(i);
+        let mut to = descendants[j].clone();
+        to.insert(j);
+
+        for k in from.ones() {
+            descendants[k].union_with(&to);
+        }
+
+        for k in to.ones() {
+            ancestors[k].union_with(&from);
+        }
+    }
+
+    /// Returns a lower bound on the total weighted tardiness of the unscheduled jobs
+    /// when they start at `time`, or `None` if they cannot be scheduled.
+    ///
+    /// `predecessors` and `successors` are the transitive closure returned by `extract_precedence_closure`,
+    /// which is used only by the assignment bound.
+    pub fn compute_dual_bound(
+        &self,
+        bound: &BoundChoice,
+        unscheduled: &FixedBitSet,
+        time: i32,
+        predecessors: &[FixedBitSet],
+        successors: &[FixedBitSet],
+    ) -> Option<i32> {
+        match bound {
+            BoundChoice::Zero => Some(0),
+            BoundChoice::Edd => Some(self.compute_edd_bound(unscheduled, time)),
+            BoundChoice::Lagrangian => Some(self.compute_lagrangian_bound(unscheduled, time)),
+            BoundChoice::Assignment => {
+                self.compute_assignment_bound(unscheduled, time, predecessors, successors)
+            }
+        }
+    }
+
+    /// Returns a lower bound on the total weighted tardiness of the unscheduled jobs
+    /// when each of them starts at `time` or later on any number of machines.
+    ///
+    /// Each job is relaxed to start as early as possible independently of the others.
+    pub fn compute_individual_bound(&self, unscheduled: &FixedBitSet, time: i32) -> i32 {
+        unscheduled
+            .ones()
+            .map(|i| {
+                let completion_time =
+                    cmp::max(time, self.release_dates[i]) + self.processing_times[i];
+
+                self.weights[i] * cmp::max(0, completion_time - self.deadlines[i])
+            })
+            .sum()
+    }
+
+    // In any schedule, the k-th smallest completion time is not smaller than that in the SPT order,
+    // and pairing the sorted completion times with the sorted deadlines minimizes the total tardiness.
+    // The total tardiness is multiplied by the minimum weight.
+    fn compute_edd_bound(&self, unscheduled: &FixedBitSet, time: i32) -> i32 {
+        let Some(min_weight) = unscheduled.ones().map(|i| self.weights[i]).min() else {
+            return 0;
+        };
+
+        let mut processing_times = unscheduled
+            .ones()
+            .map(|i| self.processing_times[i])
+            .collect::<Vec<_>>();
+        processing_times.sort_unstable();
+        let mut deadlines = unscheduled
+            .ones()
+            .map(|i| self.deadlines[i])
+            .collect::<Vec<_>>();
+        deadlines.sort_unstable();
+
+        let mut completion_time = time;
+        let tardiness = processing_times
+            .into_iter()
+            .zip(deadlines)
+            .map(|(p, d)| {
+                completion_time += p;
+
+                cmp::max(0, completion_time - d)
+            })
+            .sum::<i32>();
+
+        min_weight * tardiness
+    }
+
+    // Relaxes the tardiness constraints with multipliers between 0 and the weights,
+    // so the total weighted tardiness is at least the sum of the multipliers times the lateness,
+    // which is minimized by the WSPT rule with the multipliers as the weights.
+    //
+    // The multipliers start from the weights of the jobs that are tardy even if scheduled first,
+    // and they are updated by a few subgradient steps.
+    fn compute_lagrangian_bound(&self, unscheduled: &FixedBitSet, time: i32) -> i32 {
+        const ITERATIONS: usize = 10;
+
+        let jobs = unscheduled.ones().collect::<Vec<_>>();
+        let processing_times = jobs
+            .iter()
+            .map(|&i| self.processing_times[i])
+            .collect::<Vec<_>>();
+        let weights = jobs
+            .iter()
+            .map(|&i| f64::from(self.weights[i]))
+            .collect::<Vec<_>>();
+        let mut multipliers = jobs
+            .iter()
+            .map(|&i| {
+                if time + self.processing_times[i] > self.deadlines[i] {
+                    f64::from(self.weights[i])
+                } else {
+                    0.0
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut order = (0..jobs.len()).collect::<Vec<_>>();
+        let mut lateness = vec![0; jobs.len()];
+        let mut best_bound = 0.0;
+
+        for k in 0..ITERATIONS {
+            order.sort_by(|&a, &b| {
+                let ratio_a = f64::from(processing_times[a]) * multipliers[b];
+                let ratio_b = f64::from(processing_times[b]) * multipliers[a];
+
+                ratio_a.partial_cmp(&ratio_b).unwrap()
+            });
+
+            let mut completion_time = time;
+            let mut bound = 0.0;
+
+            for &a in &order {
+                completion_time += processing_times[a];
+                lateness[a] = completion_time - self.deadlines[jobs[a]];
+                bound += multipliers[a] * f64::from(lateness[a]);
+            }
+
+            if bound > best_bound {
+                best_bound = bound;
+            }
+
+            let step = 1.0 / (k + 2) as f64;
+
+            for (a, multiplier) in multipliers.iter_mut().enumerate() {
+                let direction = f64::from(lateness[a].signum());
+                *multiplier = (*multiplier + step * weights[a] * direction).clamp(0.0, weights[a]);
+            }
+        }
+
+        // The cost is an integer, so the bound is rounded up after removing numerical errors.
+        (best_bound - 1e-6).ceil() as i32
+    }
+
+    // Assigns the unscheduled jobs to positions, where the completion time of a job at a position
+    // is at least its processing time plus the smallest processing times of the other jobs before it.
+    // A job cannot be placed before its unscheduled predecessors or after its unscheduled successors.
+    fn compute_assignment_bound(
+        &self,
+        unscheduled: &FixedBitSet,
+        time: i32,
+        predecessors: &[FixedBitSet],
+        successors: &[FixedBitSet],
+    ) -> Option<i32> {
+        let n = self.processing_times.len();
+        let jobs = unscheduled.ones().collect::<Vec<_>>();
+        let m = jobs.len();
+
+        let mut sorted_jobs = jobs.clone();
+        sorted_jobs.sort_by_key(|&i| self.processing_times[i]);
+        let mut rank = vec![0; n];
+        let mut prefix_sums = vec![0; m + 1];
+
+        for (k, &i) in sorted_jobs.iter().enumerate() {
+            rank[i] = k;
+            prefix_sums[k + 1] = prefix_sums[k] + self.processing_times[i];
+        }
+
+        let mut earliest = vec![0; n];
+        let mut latest = vec![0; n];
+
+        for &i in &jobs {
+            earliest[i] = predecessors[i].intersection(unscheduled).count();
+            latest[i] = m - 1 - successors[i].intersection(unscheduled).count();
+        }
+
+        let weight = |i: usize, k: usize| {
+            if k < earliest[i] || k > latest[i] {
+                return None;
+            }
+
+            let others = if rank[i] < k {
+                prefix_sums[k + 1] - self.processing_times[i]
+            } else {
+                prefix_sums[k]
+            };
+            let tardiness = time + others + self.processing_times[i] - self.deadlines[i];
+
+            Some(self.weights[i] * cmp::max(0, tardiness))
+        };
+        let positions = (0..m).collect::<Vec<_>>();
+
+        io_util::solve_assignment_problem(&jobs, &positions, weight, &AssignmentSolution::new(n))
+            .map(|solution| solution.cost)
+    }
+}
+
+/// Solves the instances selected by the arguments one by one.
+///
+/// `solve` takes an instance, the file to save the history, and the file to dump the precedence graph,
+/// and returns `None` if the instance is not supported.
+/// When multiple instances are solved, the index of each instance is appended to the file names,
+/// and the results are summarized at the end.
+pub fn solve_instances(
+    args: &Args,
+    solve: impl Fn(Instance, &str, Option<&str>) -> Option<InstanceResult<i32>>,
+) -> Result<(), Box<dyn Error>> {
+    let instances = Instance::load(
+        &args.input_file,
+        args.orlib_jobs,
+        args.instance,
+        args.machines,
+        args.machines_in_file,
+        args.release_dates_in_file,
+        args.setup_times.as_deref(),
+    )?;
+
+    io_util::solve_instances(
+        instances,
+        |instance, index| {
+            let history = io_util::add_index_to_filename(&args.history, index);
+            let dump_preprocessing = args
+                .dump_preprocessing
+                .as_ref()
+                .map(|filename| io_util::add_index_to_filename(filename, index));
+
+            solve(instance, &history, dump_preprocessing.as_deref())
+        },
+        |result| result.summarize("cost"),
+    );
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum SolverChoice {
+    Cabs,
+    Astar,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum BoundChoice {
+    Zero,
+    Edd,
+    Lagrangian,
+    Assignment,
+}
+
+#[derive(Debug, Parser)]
+pub struct Args {
+    #[arg(help = "Input file")]
+    pub input_file: String,
+    #[arg(short, long, value_enum, default_value_t = SolverChoice::Cabs, help = "Solver")]
+    pub solver: SolverChoice,
+    #[arg(long, default_value_t = String::from("history.csv"), help = "File to save the history")]
+    pub history: String,
+    #[arg(short, long, default_value_t = 1800.0, help = "Time limit")]
+    pub time_limit: f64,
+    #[arg(
+        long,
+        help = "Read the input file in the OR-Library format with the given number of jobs per instance"
+    )]
+    pub orlib_jobs: Option<usize>,
+    #[arg(
+        long,
+        help = "Index of the instance in an OR-Library file starting from 1 (all instances if omitted)"
+    )]
+    pub instance: Option<usize>,
+    #[arg(
+        long,
+        help = "Number of machines (read from the file with --machines-in-file, one otherwise, if omitted); instances with multiple machines are skipped except by wt_parallel_rpid"
+    )]
+    pub machines: Option<usize>,
+    #[arg(
+        long,
+        action,
+        conflicts_with = "orlib_jobs",
+        help = "Read the number of machines following the number of jobs in the input file"
+    )]
+    pub machines_in_file: bool,
+    #[arg(
+        long,
+        action,
+        conflicts_with = "orlib_jobs",
+        help = "Read the release date of each job after its weight in the input file; instances with release dates are skipped except by wt_parallel_rpid and wt_setup_rpid"
+    )]
+    pub release_dates_in_file: bool,
+    #[arg(
+        long,
+        help = "File of sequence-dependent setup times; instances with setup times are skipped except by wt_setup_rpid"
+    )]
+    pub setup_times: Option<String>,
+    #[arg(long, value_enum, default_value_t = BoundChoice::Lagrangian, help = "Dual bound of the RPID models")]
+    pub bound: BoundChoice,
+    #[arg(
+        long,
+        help = "Prefix of files to dump the inferred precedence graph in the DOT and JSON formats (<prefix>.dot and <prefix>.json)"
+    )]
+    pub dump_preprocessing: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    fn create_instance(jobs: &[(i32, i32, i32)]) -> Instance {
+        let n = jobs.len();
+
+        Instance {
+            processing_times: jobs.iter().map(|&(p, _, _)| p).collect(),
+            deadlines: jobs.iter().map(|&(_, d, _)| d).collect(),
+            weights: jobs.iter().map(|&(_, _, w)| w).collect(),
+            release_dates: vec![0; n],
+            n_machines: 1,
+            setup_times: vec![],
+            initial_setup_times: vec![0; n],
+        }
+    }
+
+    // Generates an instance in the same way as the OR-Library instances
+    // with a linear congruential generator.
+    fn generate_instance(seed: u64, n: usize, tardiness_factor: f64, range: f64) -> Instance {
+        let mut state = seed;
+        let mut next = |lb: i32, ub: i32| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+
+            lb + ((state >> 33) % (ub - lb + 1) as u64) as i32
+        };
+
+        let processing_times = (0..n).map(|_| next(1, 100)).collect::<Vec<_>>();
+        let total = processing_times.iter().sum::<i32>() as f64;
+        let lb = (total * (1.0 - tardiness_factor - range / 2.0)).max(0.0) as i32;
+        let ub = (total * (1.0 - tardiness_factor + range / 2.0)) as i32;
+        let jobs = processing_times
+            .into_iter()
+            .map(|p| (p, next(lb, ub), next(1, 10)))
+            .collect::<Vec<_>>();
+
+        create_instance(&jobs)
+    }
+
     fn has_path(i: usize, j: usize, successors: &[FixedBitSet]) -> bool {
         let mut open = VecDeque::from([i]);
         let mut checked = vec![false; successors.len()];
@@ -94,8 +879,9 @@ impl Instance {
         false
     }
 
+    // The Kanet conditions computing the sums of processing times from the sets for each pair.
     fn check_kanet_conditions(
-        &self,
+        instance: &Instance,
         i: usize,
         predecessors_i: &FixedBitSet,
         successors_i: &FixedBitSet,
@@ -103,20 +889,20 @@ impl Instance {
         predecessors_j: &FixedBitSet,
         successors_j: &FixedBitSet,
     ) -> bool {
-        let p_i = self.processing_times[i];
-        let d_i = self.deadlines[i];
-        let w_i = self.weights[i];
-        let p_j = self.processing_times[j];
-        let d_j = self.deadlines[j];
-        let w_j = self.weights[j];
+        let p_i = instance.processing_times[i];
+        let d_i = instance.deadlines[i];
+        let w_i = instance.weights[i];
+        let p_j = instance.processing_times[j];
+        let d_j = instance.deadlines[j];
+        let w_j = instance.weights[j];
 
         let p_predecessors_j = predecessors_j
             .ones()
-            .map(|k| self.processing_times[k])
+            .map(|k| instance.processing_times[k])
             .sum::<i32>();
         let p_common = predecessors_i
             .union(predecessors_j)
-            .map(|k| self.processing_times[k])
+            .map(|k| instance.processing_times[k])
             .sum::<i32>()
             + p_i
             + p_j;
@@ -133,13 +919,13 @@ impl Instance {
 
         let p_not_successors_i = successors_i
             .zeroes()
-            .map(|k| self.processing_times[k])
+            .map(|k| instance.processing_times[k])
             .sum::<i32>();
         let mut successors_i_or_j = successors_i.clone();
         successors_i_or_j.union_with(successors_j);
         let p_not_successors_i_or_j = successors_i_or_j
             .zeroes()
-            .map(|k| self.processing_times[k])
+            .map(|k| instance.processing_times[k])
             .sum::<i32>();
 
         let k2_common = (w_j - w_i) * p_not_successors_i;
@@ -181,15 +967,12 @@ impl Instance {
             return true;
         }
 
-        if d_j >= p_not_successors_i {
-            return true;
-        }
-
-        false
+        d_j >= p_not_successors_i
     }
 
-    pub fn extract_precedence(&self) -> (Vec<FixedBitSet>, Vec<FixedBitSet>) {
-        let n = self.processing_times.len();
+    // The previous implementation searching for a path to avoid cycles for each pair.
+    fn extract_precedence_by_search(instance: &Instance) -> (Vec<FixedBitSet>, Vec<FixedBitSet>) {
+        let n = instance.processing_times.len();
         let mut predecessors = vec![FixedBitSet::with_capacity(n); n];
         let mut successors = vec![FixedBitSet::with_capacity(n); n];
         let mut change = true;
@@ -201,8 +984,9 @@ impl Instance {
                 for j in 0..n {
                     if i != j
                         && !predecessors[j].contains(i)
-                        && !Self::has_path(j, i, &successors)
-                        && self.check_kanet_conditions(
+                        && !has_path(j, i, &successors)
+                        && check_kanet_conditions(
+                            instance,
                             i,
                             &predecessors[i],
                             &successors[i],
@@ -221,22 +1005,98 @@ impl Instance {
 
         (predecessors, successors)
     }
-}
 
-#[derive(Debug, Clone, ValueEnum)]
-pub enum SolverChoice {
-    Cabs,
-    Astar,
-}
+    fn assert_same_precedence(instance: &Instance) {
+        let (expected_predecessors, expected_successors) = extract_precedence_by_search(instance);
 
-#[derive(Debug, Parser)]
-pub struct Args {
-    #[arg(help = "Input file")]
-    pub input_file: String,
-    #[arg(short, long, value_enum, default_value_t = SolverChoice::Cabs, help = "Solver")]
-    pub solver: SolverChoice,
-    #[arg(long, default_value_t = String::from("history.csv"), help = "File to save the history")]
-    pub history: String,
-    #[arg(short, long, default_value_t = 1800.0, help = "Time limit")]
-    pub time_limit: f64,
+        let (predecessors, successors) = instance.extract_precedence();
+        assert_eq!(predecessors, expected_predecessors);
+        assert_eq!(successors, expected_successors);
+
+        let (ancestors, descendants) = instance.extract_precedence_closure();
+
+        for (i, descendants_i) in descendants.iter().enumerate() {
+            for (j, ancestors_j) in ancestors.iter().enumerate() {
+                let reachable = has_path(i, j, &expected_successors);
+                assert_eq!(descendants_i.contains(j), reachable);
+                assert_eq!(ancestors_j.contains(i), reachable);
+            }
+        }
+    }
+
+    #[test]
+    fn precedence_of_hand_made_instances() {
+        // All jobs are tardy, so the WSPT order is inferred.
+        assert_same_precedence(&create_instance(&[
+            (3, 0, 1),
+            (1, 0, 2),
+            (2, 0, 2),
+            (4, 0, 3),
+        ]));
+        // All jobs are on time in any order.
+        assert_same_precedence(&create_instance(&[(3, 100, 1), (1, 100, 2), (2, 100, 2)]));
+        // Identical jobs, where only one direction of each pair can be inferred.
+        assert_same_precedence(&create_instance(&[
+            (5, 7, 2),
+            (5, 7, 2),
+            (5, 7, 2),
+            (5, 7, 2),
+        ]));
+        // A chain inferred from deadlines and a job with a late deadline.
+        assert_same_precedence(&create_instance(&[
+            (2, 2, 1),
+            (2, 4, 1),
+            (2, 6, 1),
+            (2, 8, 1),
+            (10, 30, 5),
+        ]));
+    }
+
+    #[test]
+    fn precedence_of_random_instances() {
+        let mut seed = 0;
+
+        for n in [5, 10, 20, 40] {
+            for tardiness_factor in [0.2, 0.4, 0.6, 0.8, 1.0] {
+                for range in [0.2, 0.6, 1.0] {
+                    seed += 1;
+                    assert_same_precedence(&generate_instance(seed, n, tardiness_factor, range));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn transitive_closure_of_random_arcs() {
+        let n = 12;
+        let mut state = 1u64;
+
+        for _ in 0..50 {
+            let mut successors = vec![FixedBitSet::with_capacity(n); n];
+            let mut ancestors = vec![FixedBitSet::with_capacity(n); n];
+            let mut descendants = vec![FixedBitSet::with_capacity(n); n];
+
+            // Arcs from a larger index to a smaller one are added in a random order.
+            for _ in 0..20 {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                let i = (state >> 33) as usize % n;
+                let j = (state >> 45) as usize % n;
+
+                if i > j && !successors[i].contains(j) {
+                    successors[i].insert(j);
+                    Instance::add_to_transitive_closure(i, j, &mut ancestors, &mut descendants);
+                }
+
+                for (k, descendants_k) in descendants.iter().enumerate() {
+                    for (l, ancestors_l) in ancestors.iter().enumerate() {
+                        let reachable = has_path(k, l, &successors);
+                        assert_eq!(descendants_k.contains(l), reachable);
+                        assert_eq!(ancestors_l.contains(k), reachable);
+                    }
+                }
+            }
+        }
+    }
 }